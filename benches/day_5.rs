@@ -0,0 +1,77 @@
+//! Compares freshness lookups of day 5: linear scan, binary search and sorted sweep.
+//! Run with `cargo bench --bench day_5`.
+
+use aoc_2025::range::{Range, RangeSet};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const NUM_RANGES: usize = 5_000;
+const NUM_INGREDIENTS: usize = 2_000_000;
+const MAX_ID: usize = 1 << 40;
+
+/// Small deterministic pseudo-random generator (xorshift), good enough for benchmark data
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % max as u64) as usize
+    }
+}
+
+fn time<T>(name: &str, f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(f());
+    let elapsed = start.elapsed();
+    println!("{:<30} {:>10.2?}", name, elapsed);
+    elapsed
+}
+
+fn main() {
+    let mut rng = XorShift(0x2025);
+
+    let ranges: Vec<Range> = (0..NUM_RANGES)
+        .map(|_| {
+            let start = rng.next(MAX_ID);
+            Range::new(start, start + rng.next(MAX_ID / NUM_RANGES)).unwrap()
+        })
+        .collect();
+    let fresh_ingredients = RangeSet::from_ranges(ranges);
+
+    let ingredients: Vec<usize> = (0..NUM_INGREDIENTS).map(|_| rng.next(MAX_ID)).collect();
+    let mut sorted_ingredients = ingredients.clone();
+    sorted_ingredients.sort();
+
+    println!(
+        "{} merged ranges, {} ingredients",
+        fresh_ingredients.ranges().len(),
+        NUM_INGREDIENTS
+    );
+
+    // Linear scan is slow, only run it on a sample and extrapolate
+    let sample = &ingredients[..NUM_INGREDIENTS / 100];
+    let linear = time("linear scan (1% sample)", || {
+        sample
+            .iter()
+            .filter(|&&id| fresh_ingredients.ranges().iter().any(|r| r.is_included(id)))
+            .count()
+    });
+    println!(
+        "{:<30} {:>10.2?}",
+        "linear scan (extrapolated)",
+        linear * 100
+    );
+
+    time("binary search", || {
+        ingredients
+            .iter()
+            .filter(|&&id| fresh_ingredients.contains(id))
+            .count()
+    });
+
+    time("sorted sweep", || {
+        fresh_ingredients.count_contained_sorted(&sorted_ingredients)
+    });
+}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = aoc_2025::day_1::parse_puzzle(input);
+        let _ = aoc_2025::day_1::parse_dial_instructions(input);
+    }
+});
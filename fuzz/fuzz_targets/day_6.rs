@@ -0,0 +1,16 @@
+#![no_main]
+
+use aoc_2025::day_6::{OperatorRow, ParseOptions, parse_puzzle_with_options};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        for operator_row in [OperatorRow::Bottom, OperatorRow::Top] {
+            let options = ParseOptions {
+                operator_row,
+                ..ParseOptions::default()
+            };
+            let _ = parse_puzzle_with_options(input, &options);
+        }
+    }
+});
@@ -10,7 +10,25 @@ pub fn read_input(day: &str) -> Result<String> {
     Ok(content)
 }
 
+#[derive(Debug)]
 pub struct Solution {
     pub task_1: usize,
     pub task_2: usize,
 }
+
+/// Lets solvers return either a `Solution` or a `Result<Solution>` when solving can fail
+pub trait IntoSolution {
+    fn into_solution(self) -> Result<Solution>;
+}
+
+impl IntoSolution for Solution {
+    fn into_solution(self) -> Result<Solution> {
+        Ok(self)
+    }
+}
+
+impl IntoSolution for Result<Solution> {
+    fn into_solution(self) -> Result<Solution> {
+        self
+    }
+}
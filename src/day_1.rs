@@ -11,6 +11,7 @@ pub struct Puzzle {
     instructions: Vec<Instruction>,
 }
 
+#[derive(Clone, Debug)]
 enum Instruction {
     Left(usize),
     Right(usize),
@@ -24,6 +25,14 @@ impl Instruction {
         }
     }
 
+    /// Instruction in the same direction as `other`, with `steps` steps
+    fn new_like(other: &Instruction, steps: usize) -> Instruction {
+        match other {
+            Instruction::Left(_) => Instruction::Left(steps),
+            Instruction::Right(_) => Instruction::Right(steps),
+        }
+    }
+
     fn delta(&self) -> i64 {
         match self {
             Instruction::Left(steps) => -(*steps as i64),
@@ -44,58 +53,45 @@ impl Display for Instruction {
 pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     let instructions: Vec<Instruction> = input
         .lines()
-        .map(|line| {
-            if line.is_empty() {
-                return Err(anyhow!("Empty line found"));
-            }
-
-            let (dir, value) = line.split_at(1);
-            let value: usize = value
-                .parse()
-                .with_context(|| format!("Failed to parse number in line: '{}'", line))?;
-
-            match dir {
-                "L" => Ok(Instruction::Left(value)),
-                "R" => Ok(Instruction::Right(value)),
-                _ => Err(anyhow!(
-                    "Invalid direction. Expected 'L' or 'R', found '{}'",
-                    dir
-                )),
-            }
-        })
+        .map(parse_instruction)
         // Use collect to gather results and propagate errors if any
         .collect::<Result<Vec<Instruction>>>()?;
 
     Ok(Puzzle { instructions })
 }
 
+fn parse_instruction(line: &str) -> Result<Instruction> {
+    if line.is_empty() {
+        return Err(anyhow!("Empty line found"));
+    }
+
+    // Split after the first character, which may be several bytes long
+    let dir_length = line.chars().next().map_or(0, char::len_utf8);
+    let (dir, value) = line.split_at(dir_length);
+    let value: usize = value
+        .parse()
+        .with_context(|| format!("Failed to parse number in line: '{}'", line))?;
+
+    match dir {
+        "L" => Ok(Instruction::Left(value)),
+        "R" => Ok(Instruction::Right(value)),
+        _ => Err(anyhow!(
+            "Invalid direction. Expected 'L' or 'R', found '{}'",
+            dir
+        )),
+    }
+}
+
 pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
     let mut times_finish_at_zero: usize = 0;
     let mut times_pass_zero: usize = 0;
 
-    let mut position = START_POSITION as i64;
+    let mut position = START_POSITION;
 
     for instruction in &puzzle.instructions {
-        let old_position = position;
-        let new_position = (old_position + instruction.delta()).rem_euclid(MAX_POSITION as i64);
+        let (new_position, passes) = rotate(position, MAX_POSITION, instruction);
         position = new_position;
-
-        // Number of time we go full circle
-        times_pass_zero += instruction.steps() / MAX_POSITION;
-
-        // Check if we passed zero in this move (not counting full circles)
-        match instruction {
-            Instruction::Left(_) => {
-                if (new_position > old_position || new_position == 0) && old_position != 0 {
-                    times_pass_zero += 1;
-                }
-            }
-            Instruction::Right(_) => {
-                if new_position < old_position {
-                    times_pass_zero += 1;
-                }
-            }
-        }
+        times_pass_zero += passes;
 
         // Check if we finish at zero
         if new_position == 0 {
@@ -109,9 +105,201 @@ pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
     }
 }
 
+/// Rotates a dial with `size` positions, starting at `position`.
+/// Returns (new position, number of times the dial pointed at zero during the move)
+fn rotate(position: usize, size: usize, instruction: &Instruction) -> (usize, usize) {
+    let old_position = position as i64;
+    let new_position = (old_position + instruction.delta()).rem_euclid(size as i64);
+
+    // Number of time we go full circle
+    let mut times_pass_zero = instruction.steps() / size;
+
+    // Check if we passed zero in this move (not counting full circles)
+    match instruction {
+        Instruction::Left(_) => {
+            if (new_position > old_position || new_position == 0) && old_position != 0 {
+                times_pass_zero += 1;
+            }
+        }
+        Instruction::Right(_) => {
+            if new_position < old_position {
+                times_pass_zero += 1;
+            }
+        }
+    }
+
+    (new_position as usize, times_pass_zero)
+}
+
+/// Instruction for one of the dials of a [`Lock`], written `A:L20`
+pub struct DialInstruction {
+    dial: char,
+    instruction: Instruction,
+}
+
+impl Display for DialInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.dial, self.instruction)
+    }
+}
+
+pub fn parse_dial_instructions(input: &str) -> Result<Vec<DialInstruction>> {
+    input
+        .lines()
+        .map(|line| {
+            let (dial, instruction) = line.split_once(':').with_context(|| {
+                format!(
+                    "Invalid dial instruction, expected 'A:L20', found '{}'",
+                    line
+                )
+            })?;
+
+            let mut chars = dial.chars();
+            let dial = match (chars.next(), chars.next()) {
+                (Some(dial), None) => dial,
+                _ => {
+                    return Err(anyhow!(
+                        "Invalid dial name. Expected a single character, found '{}'",
+                        dial
+                    ));
+                }
+            };
+
+            Ok(DialInstruction {
+                dial,
+                instruction: parse_instruction(instruction)?,
+            })
+        })
+        .collect()
+}
+
+struct Dial {
+    name: char,
+    position: usize,
+    /// Dial advanced by one step every time this dial points at zero
+    drives: Option<usize>,
+}
+
+/// Lock made of several concentric dials, all starting at `START_POSITION`.
+/// Dials can be coupled: each time a dial points at zero, the dial it drives moves one step in the same direction.
+pub struct Lock {
+    dials: Vec<Dial>,
+}
+
+impl Lock {
+    pub fn new(names: &[char]) -> Result<Lock> {
+        let mut dials: Vec<Dial> = Vec::with_capacity(names.len());
+
+        for &name in names {
+            if dials.iter().any(|dial| dial.name == name) {
+                return Err(anyhow!("Duplicate dial '{}'", name));
+            }
+            dials.push(Dial {
+                name,
+                position: START_POSITION,
+                drives: None,
+            });
+        }
+
+        Ok(Lock { dials })
+    }
+
+    fn dial_index(&self, name: char) -> Result<usize> {
+        self.dials
+            .iter()
+            .position(|dial| dial.name == name)
+            .with_context(|| format!("Unknown dial '{}'", name))
+    }
+
+    /// Couple `driver` to `driven`: every time `driver` points at zero, `driven` moves one step.
+    /// Fails if the coupling would create a cycle (which would never stop rotating).
+    pub fn couple(&mut self, driver: char, driven: char) -> Result<()> {
+        let driver_index = self.dial_index(driver)?;
+        let driven_index = self.dial_index(driven)?;
+
+        // Follow the chain from the driven dial, it must not lead back to the driver
+        let mut next = Some(driven_index);
+        while let Some(index) = next {
+            if index == driver_index {
+                return Err(anyhow!(
+                    "Coupling '{}' to '{}' creates a cycle",
+                    driver,
+                    driven
+                ));
+            }
+            next = self.dials[index].drives;
+        }
+
+        self.dials[driver_index].drives = Some(driven_index);
+        Ok(())
+    }
+
+    /// Positions of the dials, in the order they were given to [`Lock::new`]
+    pub fn positions(&self) -> Vec<usize> {
+        self.dials.iter().map(|dial| dial.position).collect()
+    }
+
+    pub fn apply(&mut self, dial_instruction: &DialInstruction) -> Result<()> {
+        let mut index = self.dial_index(dial_instruction.dial)?;
+        let mut instruction = dial_instruction.instruction.clone();
+
+        // Propagate rotation through coupled dials (the chain has no cycle, see `couple`)
+        loop {
+            let dial = &mut self.dials[index];
+            let (position, passes) = rotate(dial.position, MAX_POSITION, &instruction);
+            dial.position = position;
+
+            match dial.drives {
+                Some(driven) if passes > 0 => {
+                    index = driven;
+                    instruction = Instruction::new_like(&instruction, passes);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Applies instructions one by one and returns the length of the first prefix after which
+    /// the dials show `combination`, or `None` if the lock never opens.
+    pub fn first_opening_prefix(
+        &mut self,
+        instructions: &[DialInstruction],
+        combination: &[usize],
+    ) -> Result<Option<usize>> {
+        if combination.len() != self.dials.len() {
+            return Err(anyhow!(
+                "Combination has {} values but the lock has {} dials",
+                combination.len(),
+                self.dials.len()
+            ));
+        }
+
+        let is_open = |lock: &Lock| {
+            lock.dials
+                .iter()
+                .map(|d| d.position)
+                .eq(combination.iter().copied())
+        };
+
+        if is_open(self) {
+            return Ok(Some(0));
+        }
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            self.apply(instruction)?;
+            if is_open(self) {
+                return Ok(Some(i + 1));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_example_puzzle() {
@@ -212,6 +400,97 @@ mod tests {
         assert_eq!(solution.task_2, 1);
     }
 
+    #[test]
+    fn test_lock_single_dial_matches_puzzle() {
+        let input = include_str!("../inputs/examples/day_1");
+        let instructions: Vec<String> = input.lines().map(|l| format!("A:{}", l)).collect();
+        let instructions = parse_dial_instructions(&instructions.join("\n")).unwrap();
+        let mut lock = Lock::new(&['A']).unwrap();
+        // The example finishes at zero for the first time after 3 instructions
+        assert_eq!(
+            lock.first_opening_prefix(&instructions, &[0]).unwrap(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_lock_coupled_dials() {
+        let instructions = parse_dial_instructions("A:R250\nB:L3\nA:L60").unwrap();
+        let mut lock = Lock::new(&['A', 'B', 'C']).unwrap();
+        lock.couple('A', 'B').unwrap();
+        lock.couple('B', 'C').unwrap();
+
+        lock.apply(&instructions[0]).unwrap();
+        // A passes zero 3 times -> B moves 3 steps right
+        assert_eq!(lock.positions(), vec![0, 53, 50]);
+
+        lock.apply(&instructions[1]).unwrap();
+        assert_eq!(lock.positions(), vec![0, 50, 50]);
+
+        lock.apply(&instructions[2]).unwrap();
+        // A starts at zero, does not pass it again
+        assert_eq!(lock.positions(), vec![40, 50, 50]);
+    }
+
+    #[test]
+    fn test_lock_coupling_cascade() {
+        let mut lock = Lock::new(&['A', 'B', 'C']).unwrap();
+        lock.couple('A', 'B').unwrap();
+        lock.couple('B', 'C').unwrap();
+
+        // A passes zero 50 times -> B moves R50 and reaches zero once -> C moves R1
+        let instructions = parse_dial_instructions("A:R4950").unwrap();
+        lock.apply(&instructions[0]).unwrap();
+        assert_eq!(lock.positions(), vec![0, 0, 51]);
+    }
+
+    #[test]
+    fn test_lock_coupling_cycle() {
+        let mut lock = Lock::new(&['A', 'B', 'C']).unwrap();
+        lock.couple('A', 'B').unwrap();
+        lock.couple('B', 'C').unwrap();
+        let result = lock.couple('C', 'A');
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Coupling 'C' to 'A' creates a cycle")
+        );
+    }
+
+    #[test]
+    fn test_lock_first_opening_prefix() {
+        let instructions = parse_dial_instructions("A:L10\nB:R10").unwrap();
+        let mut lock = Lock::new(&['A', 'B']).unwrap();
+        assert_eq!(
+            lock.first_opening_prefix(&instructions, &[40, 50]).unwrap(),
+            Some(1)
+        );
+        let mut lock = Lock::new(&['A', 'B']).unwrap();
+        assert_eq!(
+            lock.first_opening_prefix(&instructions, &[0, 0]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_dial_instructions_invalid() {
+        assert!(parse_dial_instructions("L20").is_err());
+        assert!(parse_dial_instructions("AB:L20").is_err());
+        assert!(parse_dial_instructions("A:X20").is_err());
+    }
+
+    #[test]
+    fn test_parse_multibyte_direction() {
+        let error = parse_puzzle("é20").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid direction. Expected 'L' or 'R', found 'é'"
+        );
+        assert!(parse_dial_instructions("A:€").is_err());
+    }
+
     #[test]
     fn test_real_input() {
         let input = include_str!("../inputs/day_1");
@@ -220,4 +499,52 @@ mod tests {
         assert_eq!(solution.task_1, 1145);
         assert_eq!(solution.task_2, 6561);
     }
+
+    /// Reference implementation: moves the dial one click at a time
+    fn solve_puzzle_naive(instructions: &[Instruction]) -> Solution {
+        let mut position = START_POSITION;
+        let mut solution = Solution {
+            task_1: 0,
+            task_2: 0,
+        };
+
+        for instruction in instructions {
+            for _ in 0..instruction.steps() {
+                position = match instruction {
+                    Instruction::Left(_) => (position + MAX_POSITION - 1) % MAX_POSITION,
+                    Instruction::Right(_) => (position + 1) % MAX_POSITION,
+                };
+                if position == 0 {
+                    solution.task_2 += 1;
+                }
+            }
+            if position == 0 {
+                solution.task_1 += 1;
+            }
+        }
+
+        solution
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        (any::<bool>(), 0..350usize).prop_map(|(left, steps)| {
+            if left {
+                Instruction::Left(steps)
+            } else {
+                Instruction::Right(steps)
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(instructions in prop::collection::vec(instruction(), 0..50)) {
+            let input: Vec<String> = instructions.iter().map(|i| i.to_string()).collect();
+            let puzzle = parse_puzzle(&input.join("\n")).unwrap();
+            let solution = solve_puzzle(puzzle);
+            let expected = solve_puzzle_naive(&instructions);
+            prop_assert_eq!(solution.task_1, expected.task_1);
+            prop_assert_eq!(solution.task_2, expected.task_2);
+        }
+    }
 }
@@ -4,7 +4,7 @@ use anyhow::Result;
 
 use crate::{
     common::Solution,
-    range::{Range, merge_ranges},
+    range::{Range, RangeSet},
 };
 
 #[derive(Debug)]
@@ -29,9 +29,9 @@ pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
     let mut invalid_sum_1 = 0;
     let mut invalid_sum_2 = 0;
 
-    let merged_ranges = merge_ranges(puzzle.ranges);
+    let ids = RangeSet::from_ranges(puzzle.ranges);
 
-    for range in &merged_ranges {
+    for range in ids.ranges() {
         for id in range.range() {
             get_digits_into(id, &mut buffer);
             if !is_id_valid_1(&buffer) {
@@ -110,6 +110,8 @@ fn is_id_valid_2(digits: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
 
     #[test]
     fn test_example_puzzle() {
@@ -122,15 +124,15 @@ mod tests {
 
     #[test]
     fn test_is_id_valid_2() {
-        assert_eq!(is_id_valid_2(&[1, 1]), false);
-        assert_eq!(is_id_valid_2(&[1, 1, 2]), true);
-        assert_eq!(is_id_valid_2(&[1, 2, 1, 2]), false);
-        assert_eq!(is_id_valid_2(&[1, 2, 1, 2, 3]), true);
-        assert_eq!(is_id_valid_2(&[1, 2, 3, 1, 2, 3]), false);
-        assert_eq!(is_id_valid_2(&[1, 2, 3, 4, 1, 2, 3, 4]), false);
-        assert_eq!(is_id_valid_2(&[1, 2, 3, 1, 2, 3, 1, 2, 3]), false);
-        assert_eq!(is_id_valid_2(&[1, 2, 1, 2, 1, 2, 1, 2, 1, 2]), false);
-        assert_eq!(is_id_valid_2(&[1, 1, 1, 1, 1, 1, 1]), false);
+        assert!(!is_id_valid_2(&[1, 1]));
+        assert!(is_id_valid_2(&[1, 1, 2]));
+        assert!(!is_id_valid_2(&[1, 2, 1, 2]));
+        assert!(is_id_valid_2(&[1, 2, 1, 2, 3]));
+        assert!(!is_id_valid_2(&[1, 2, 3, 1, 2, 3]));
+        assert!(!is_id_valid_2(&[1, 2, 3, 4, 1, 2, 3, 4]));
+        assert!(!is_id_valid_2(&[1, 2, 3, 1, 2, 3, 1, 2, 3]));
+        assert!(!is_id_valid_2(&[1, 2, 1, 2, 1, 2, 1, 2, 1, 2]));
+        assert!(!is_id_valid_2(&[1, 1, 1, 1, 1, 1, 1]));
     }
 
     #[test]
@@ -141,4 +143,45 @@ mod tests {
         assert_eq!(solution.task_1, 26255179562);
         assert_eq!(solution.task_2, 31680313976);
     }
+
+    /// Reference implementation: checks every id as a string, counting overlapping ids once
+    fn solve_puzzle_naive(ranges: &[(usize, usize)]) -> Solution {
+        let ids: BTreeSet<usize> = ranges
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .collect();
+
+        let is_invalid_1 = |id: &str| {
+            let (left, right) = id.split_at(id.len() / 2);
+            id.len().is_multiple_of(2) && left == right
+        };
+        let is_invalid_2 = |id: &str| {
+            (1..id.len())
+                .filter(|size| id.len().is_multiple_of(*size))
+                .any(|size| id[..size].repeat(id.len() / size) == id)
+        };
+
+        Solution {
+            task_1: ids.iter().filter(|id| is_invalid_1(&id.to_string())).sum(),
+            task_2: ids.iter().filter(|id| is_invalid_2(&id.to_string())).sum(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(
+            ranges in prop::collection::vec((0..200_000usize, 0..2_000usize), 1..6)
+        ) {
+            let ranges: Vec<(usize, usize)> = ranges
+                .into_iter()
+                .map(|(start, length)| (start, start + length))
+                .collect();
+            let input: Vec<String> = ranges.iter().map(|(start, end)| format!("{}-{}", start, end)).collect();
+            let puzzle = parse_puzzle(&input.join(",")).unwrap();
+            let solution = solve_puzzle(puzzle);
+            let expected = solve_puzzle_naive(&ranges);
+            prop_assert_eq!(solution.task_1, expected.task_1);
+            prop_assert_eq!(solution.task_2, expected.task_2);
+        }
+    }
 }
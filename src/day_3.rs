@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
+use num_bigint::BigUint;
+use std::fmt::Display;
 
-use crate::common::Solution;
-
-// TODO: Could remove panic!(...) in this...
+use crate::{
+    common::Solution,
+    subsequence::{digits_to_number, max_subsequence},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Puzzle {
@@ -32,76 +35,355 @@ pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     Ok(Puzzle { banks })
 }
 
-/// Solve puzzle (2 for task_1 and 12 batteries for task_1)
-pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
-    let solution_2 = solve_puzzle_num_batteries(&puzzle, 2);
-    let solution_12 = solve_puzzle_num_batteries(&puzzle, 12);
+/// Error raised when batteries can't be turned on in a bank
+#[derive(Debug, PartialEq)]
+pub enum BatteryError {
+    /// Asked to turn on 0 batteries
+    NoBatteries,
+    /// Bank at index `bank` has only `length` batteries, fewer than the `num_on_batteries` requested
+    BankTooShort {
+        bank: usize,
+        length: usize,
+        num_on_batteries: usize,
+    },
+    /// Joltage of `num_on_batteries` batteries (or their total over all banks) does not fit `usize`
+    JoltageOverflow { num_on_batteries: usize },
+}
+
+impl Display for BatteryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatteryError::NoBatteries => write!(f, "Invalid argument: 0 batteries to turn on"),
+            BatteryError::BankTooShort {
+                bank,
+                length,
+                num_on_batteries,
+            } => write!(
+                f,
+                "Bank {} has only {} batteries, cannot turn on {}",
+                bank, length, num_on_batteries
+            ),
+            BatteryError::JoltageOverflow { num_on_batteries } => write!(
+                f,
+                "Joltage of {} batteries does not fit usize, use big joltages",
+                num_on_batteries
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BatteryError {}
+
+/// What to do with banks that have fewer batteries than the number to turn on
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ShortBankPolicy {
+    /// Ignore the bank, it does not contribute to the joltage
+    Skip,
+    /// Fail with [`BatteryError::BankTooShort`]
+    #[default]
+    Error,
+    /// Turn on all batteries of the bank
+    WholeBank,
+}
+
+/// Solve puzzle (2 for task_1 and 12 batteries for task_2)
+/// Banks too short for 12 batteries turn on all their batteries.
+/// Fails if a total joltage does not fit usize.
+pub fn solve_puzzle(puzzle: Puzzle) -> Result<Solution> {
+    let solve = |num_on_batteries| {
+        solve_puzzle_num_batteries(&puzzle, num_on_batteries, ShortBankPolicy::WholeBank)
+    };
+
+    Ok(Solution {
+        task_1: solve(2)?,
+        task_2: solve(12)?,
+    })
+}
+
+/// Solve puzzle (turns on `num_on_batteries` batteries), handling short banks with `policy`
+pub fn solve_puzzle_num_batteries(
+    puzzle: &Puzzle,
+    num_on_batteries: usize,
+    policy: ShortBankPolicy,
+) -> Result<usize, BatteryError> {
+    let selections = select_batteries(puzzle, num_on_batteries, policy)?;
+
+    selections
+        .iter()
+        .flatten()
+        .try_fold(0usize, |total, selection| {
+            total
+                .checked_add(selection.joltage()?)
+                .ok_or(BatteryError::JoltageOverflow { num_on_batteries })
+        })
+}
+
+/// Solve puzzle for each number of batteries in `counts`.
+/// Totals are big integers as they overflow `usize` past 19 batteries.
+pub fn solve_puzzle_counts(
+    puzzle: &Puzzle,
+    counts: &[usize],
+    policy: ShortBankPolicy,
+) -> Result<Vec<BigUint>, BatteryError> {
+    counts
+        .iter()
+        .map(|&num_on_batteries| {
+            let selections = select_batteries(puzzle, num_on_batteries, policy)?;
+            Ok(selections
+                .iter()
+                .flatten()
+                .map(Selection::big_joltage)
+                .sum())
+        })
+        .collect()
+}
+
+/// Parse a comma separated list of battery counts, e.g. `2,5,12,20`
+pub fn parse_battery_counts(str: &str) -> Result<Vec<usize>> {
+    str.split(',')
+        .map(|count| {
+            count
+                .trim()
+                .parse()
+                .with_context(|| format!("Failed to parse battery count: '{}'", count))
+        })
+        .collect()
+}
+
+/// Batteries turned on in a bank
+#[derive(Debug, PartialEq, Clone)]
+pub struct Selection {
+    /// Positions of the batteries in the bank, in increasing order
+    pub indices: Vec<usize>,
+    /// Digits of the batteries, in the same order as `indices`
+    pub digits: Vec<usize>,
+}
+
+impl Selection {
+    /// Fails for more than 19 batteries (`usize` holds any 19 digit number), see [`Selection::big_joltage`]
+    pub fn joltage(&self) -> Result<usize, BatteryError> {
+        self.digits
+            .iter()
+            .try_fold(0usize, |acc, &value| {
+                acc.checked_mul(10)?.checked_add(value)
+            })
+            .ok_or(BatteryError::JoltageOverflow {
+                num_on_batteries: self.digits.len(),
+            })
+    }
 
-    Solution {
-        task_1: solution_2,
-        task_2: solution_12,
+    /// Joltage of any number of batteries
+    pub fn big_joltage(&self) -> BigUint {
+        digits_to_number(&self.digits)
     }
 }
 
-/// Solve puzzle (turns on `num_on_batteries` batteries)
-fn solve_puzzle_num_batteries(puzzle: &Puzzle, num_on_batteries: usize) -> usize {
+/// Select the batteries giving the largest joltage in each bank.
+/// Returns one selection per bank, `None` for banks skipped by `policy`.
+pub fn select_batteries(
+    puzzle: &Puzzle,
+    num_on_batteries: usize,
+    policy: ShortBankPolicy,
+) -> Result<Vec<Option<Selection>>, BatteryError> {
     if num_on_batteries == 0 {
-        panic!("Invalid argument: 0 batteries to turn on");
+        return Err(BatteryError::NoBatteries);
     }
 
     puzzle
         .banks
         .iter()
-        .map(|bank| find_max_batteries(&bank.batteries, num_on_batteries))
-        .sum()
+        .enumerate()
+        .map(|(index, bank)| {
+            let length = bank.batteries.len();
+
+            if length >= num_on_batteries {
+                return Ok(find_max_batteries(&bank.batteries, num_on_batteries));
+            }
+
+            match policy {
+                ShortBankPolicy::Skip => Ok(None),
+                ShortBankPolicy::Error => Err(BatteryError::BankTooShort {
+                    bank: index,
+                    length,
+                    num_on_batteries,
+                }),
+                ShortBankPolicy::WholeBank => Ok(find_max_batteries(&bank.batteries, length)),
+            }
+        })
+        .collect()
 }
 
-fn find_max_batteries(batteries: &[usize], num_on_batteries: usize) -> usize {
-    let num_batteries = batteries.len();
+/// Returns `None` if there are fewer than `num_on_batteries` batteries
+fn find_max_batteries(batteries: &[usize], num_on_batteries: usize) -> Option<Selection> {
+    let indices = max_subsequence(batteries, num_on_batteries)?;
+    let digits = indices.iter().map(|&i| batteries[i]).collect();
 
-    if num_batteries < num_on_batteries {
-        panic!(
-            "`num_on_batteries = {}`, but only have {} batteries: {:?}",
-            num_on_batteries, num_batteries, batteries
-        );
+    Some(Selection { indices, digits })
+}
+
+/// All selections of `num_on_batteries` batteries reaching the largest joltage.
+/// There are several when equal digits can be picked at different positions.
+/// Their number can grow exponentially, see [`first_max_batteries`] and [`count_max_batteries`].
+pub fn find_all_max_batteries(batteries: &[usize], num_on_batteries: usize) -> Vec<Selection> {
+    first_max_batteries(batteries, num_on_batteries, usize::MAX)
+}
+
+/// First `limit` selections of [`find_all_max_batteries`], in the same order
+pub fn first_max_batteries(
+    batteries: &[usize],
+    num_on_batteries: usize,
+    limit: usize,
+) -> Vec<Selection> {
+    let Some(best) = find_max_batteries(batteries, num_on_batteries) else {
+        return vec![];
+    };
+
+    let mut selections = Vec::new();
+    let mut indices = Vec::with_capacity(num_on_batteries);
+    collect_max_batteries(
+        batteries,
+        &best.digits,
+        0,
+        limit,
+        &mut indices,
+        &mut selections,
+    );
+    selections
+}
+
+/// Number of selections of `num_on_batteries` batteries reaching the largest joltage, without listing them
+pub fn count_max_batteries(batteries: &[usize], num_on_batteries: usize) -> BigUint {
+    let Some(best) = find_max_batteries(batteries, num_on_batteries) else {
+        return BigUint::ZERO;
+    };
+
+    // ways[j]: number of ways to pick the first j digits of the best selection among the batteries seen so far
+    let mut ways = vec![BigUint::ZERO; best.digits.len() + 1];
+    ways[0] = BigUint::from(1u32);
+
+    for battery in batteries {
+        for j in (1..=best.digits.len()).rev() {
+            if best.digits[j - 1] == *battery {
+                let previous = ways[j - 1].clone();
+                ways[j] += previous;
+            }
+        }
+    }
+
+    ways.pop().unwrap_or_default()
+}
+
+/// Depth-first search of the positions matching `digits[indices.len()]`, starting at `from`.
+/// Stops once `limit` selections are found.
+fn collect_max_batteries(
+    batteries: &[usize],
+    digits: &[usize],
+    from: usize,
+    limit: usize,
+    indices: &mut Vec<usize>,
+    selections: &mut Vec<Selection>,
+) {
+    let picked = indices.len();
+
+    if picked == digits.len() {
+        selections.push(Selection {
+            indices: indices.clone(),
+            digits: digits.to_vec(),
+        });
+        return;
     }
 
-    let mut to_remove = num_batteries - num_on_batteries;
+    // Leave enough batteries after this one for the remaining digits
+    let last = batteries.len() - (digits.len() - picked);
 
-    let mut res: Vec<usize> = vec![];
+    for index in from..=last {
+        if selections.len() >= limit {
+            return;
+        }
+        if batteries[index] == digits[picked] {
+            indices.push(index);
+            collect_max_batteries(batteries, digits, index + 1, limit, indices, selections);
+            indices.pop();
+        }
+    }
+}
+
+/// Renders every bank on its own line with the selected batteries highlighted, followed by the joltage.
+/// With `max_ties`, up to that many optimal selections of a bank are rendered, followed by the number of
+/// selections left out.
+pub fn render_selections(
+    puzzle: &Puzzle,
+    num_on_batteries: usize,
+    policy: ShortBankPolicy,
+    max_ties: Option<usize>,
+) -> Result<String, BatteryError> {
+    let selections = select_batteries(puzzle, num_on_batteries, policy)?;
+
+    let mut output = String::new();
+
+    for (bank, selection) in puzzle.banks.iter().zip(selections) {
+        let Some(selection) = selection else {
+            output.push_str(&format!(
+                "{} (skipped)\n",
+                render_bank(&bank.batteries, &[])
+            ));
+            continue;
+        };
+
+        let num_on_batteries = selection.indices.len();
+        let selections = match max_ties {
+            Some(max_ties) => first_max_batteries(&bank.batteries, num_on_batteries, max_ties),
+            None => vec![selection],
+        };
 
-    // Greedily find largest numbers by removing all previous numbers with lower value
-    // Adds between [num_batteries-to_remove = num_on_batteries, num_batteries] numbers
-    for &value in batteries {
-        // If we find a number greater than last seen value, remove stored values until either
-        // - there is nothing to remove
-        // - the last seen value is greater
-        // This makes sense because the first numbers in `res` have the most importance, if they can have a greater value, they must.
-        while to_remove > 0 && !res.is_empty() && *res.last().unwrap() < value {
-            // We remove a number here, so increase count
-            res.pop();
-            to_remove -= 1;
+        for selection in &selections {
+            output.push_str(&format!(
+                "{} -> {}\n",
+                render_bank(&bank.batteries, &selection.indices),
+                selection.big_joltage()
+            ));
         }
 
-        // Add value to res
-        res.push(value);
+        if max_ties.is_some_and(|max_ties| selections.len() == max_ties) {
+            let ties = count_max_batteries(&bank.batteries, num_on_batteries);
+            if ties > BigUint::from(selections.len()) {
+                output.push_str(&format!(
+                    "... {} more optimal selections ({} in total)\n",
+                    &ties - selections.len(),
+                    ties
+                ));
+            }
+        }
     }
 
-    // Trim what is taken in excess (happens if nothing was removed for example - list sorted in descending order)
-    res.truncate(num_on_batteries);
+    Ok(output)
+}
 
-    res.iter().fold(0, |acc, value| acc * 10 + value)
+/// Batteries at `highlighted` positions are printed in bold green
+fn render_bank(batteries: &[usize], highlighted: &[usize]) -> String {
+    batteries
+        .iter()
+        .enumerate()
+        .map(|(i, battery)| {
+            if highlighted.contains(&i) {
+                format!("\x1b[1;32m{}\x1b[0m", battery)
+            } else {
+                battery.to_string()
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_example_puzzle() {
         let input = include_str!("../inputs/examples/day_3");
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 357);
         assert_eq!(solution.task_2, 3121910778619);
     }
@@ -110,7 +392,7 @@ mod tests {
     fn test_example_puzzle_1() {
         let input = "987654321111111";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 98);
         assert_eq!(solution.task_2, 987654321111);
     }
@@ -119,7 +401,7 @@ mod tests {
     fn test_example_puzzle_2() {
         let input = "811111111111119";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 89);
         assert_eq!(solution.task_2, 811111111119);
     }
@@ -128,7 +410,7 @@ mod tests {
     fn test_example_puzzle_3() {
         let input = "234234234234278";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 78);
         assert_eq!(solution.task_2, 434234234278);
     }
@@ -137,7 +419,7 @@ mod tests {
     fn test_example_puzzle_4() {
         let input = "818181911112111";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 92);
         assert_eq!(solution.task_2, 888911112111);
     }
@@ -146,7 +428,7 @@ mod tests {
     fn test_example_puzzle_5() {
         let input = "24352342";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution_4 = solve_puzzle_num_batteries(&puzzle, 4);
+        let solution_4 = solve_puzzle_num_batteries(&puzzle, 4, ShortBankPolicy::Error).unwrap();
         assert_eq!(solution_4, 5342);
     }
 
@@ -154,10 +436,181 @@ mod tests {
     fn test_example_puzzle_6() {
         let input = "987654321";
         let puzzle = parse_puzzle(input).unwrap();
-        let solution_4 = solve_puzzle_num_batteries(&puzzle, 4);
+        let solution_4 = solve_puzzle_num_batteries(&puzzle, 4, ShortBankPolicy::Error).unwrap();
         assert_eq!(solution_4, 9876);
     }
 
+    #[test]
+    fn test_zero_batteries() {
+        let puzzle = parse_puzzle("987654321").unwrap();
+        let result = solve_puzzle_num_batteries(&puzzle, 0, ShortBankPolicy::Error);
+        assert_eq!(result, Err(BatteryError::NoBatteries));
+    }
+
+    #[test]
+    fn test_short_bank_policies() {
+        let input = "
+987654321
+12
+54321
+"
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+
+        let error = solve_puzzle_num_batteries(&puzzle, 4, ShortBankPolicy::Error);
+        assert_eq!(
+            error,
+            Err(BatteryError::BankTooShort {
+                bank: 1,
+                length: 2,
+                num_on_batteries: 4
+            })
+        );
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Bank 1 has only 2 batteries, cannot turn on 4"
+        );
+
+        let skip = solve_puzzle_num_batteries(&puzzle, 4, ShortBankPolicy::Skip);
+        assert_eq!(skip, Ok(9876 + 5432));
+
+        let whole_bank = solve_puzzle_num_batteries(&puzzle, 4, ShortBankPolicy::WholeBank);
+        assert_eq!(whole_bank, Ok(9876 + 12 + 5432));
+    }
+
+    #[test]
+    fn test_short_bank_solve_puzzle() {
+        let puzzle = parse_puzzle("12345").unwrap();
+        let solution = solve_puzzle(puzzle).unwrap();
+        assert_eq!(solution.task_1, 45);
+        assert_eq!(solution.task_2, 12345);
+    }
+
+    #[test]
+    fn test_find_max_batteries_selection() {
+        let selection = find_max_batteries(&[8, 1, 8, 1, 8, 1, 9, 1], 4).unwrap();
+        assert_eq!(selection.indices, vec![0, 2, 6, 7]);
+        assert_eq!(selection.digits, vec![8, 8, 9, 1]);
+        assert_eq!(selection.joltage(), Ok(8891));
+    }
+
+    #[test]
+    fn test_long_bank_big_joltage() {
+        let batteries: Vec<usize> = (0..100).map(|i| (i * 7) % 10).collect();
+        let selection = find_max_batteries(&batteries, 30).unwrap();
+        assert_eq!(
+            selection.big_joltage(),
+            "999999996307418529630741852963".parse::<BigUint>().unwrap()
+        );
+        assert_eq!(
+            selection.joltage(),
+            Err(BatteryError::JoltageOverflow {
+                num_on_batteries: 30
+            })
+        );
+
+        // 20 digits may still fit usize
+        let mut batteries = vec![0; 20];
+        batteries[0] = 1;
+        let selection = find_max_batteries(&batteries, 20).unwrap();
+        assert_eq!(selection.joltage(), Ok(10_000_000_000_000_000_000));
+
+        let puzzle = parse_puzzle(&"9".repeat(25)).unwrap();
+        assert_eq!(
+            solve_puzzle_num_batteries(&puzzle, 20, ShortBankPolicy::Error),
+            Err(BatteryError::JoltageOverflow {
+                num_on_batteries: 20
+            })
+        );
+        let rendered = render_selections(&puzzle, 25, ShortBankPolicy::Error, None).unwrap();
+        assert!(rendered.ends_with(&format!(" -> {}\n", "9".repeat(25))));
+    }
+
+    #[test]
+    fn test_find_all_max_batteries() {
+        let selections = find_all_max_batteries(&[9, 9, 8, 9], 2);
+        let indices: Vec<Vec<usize>> = selections.iter().map(|s| s.indices.clone()).collect();
+        assert_eq!(indices, vec![vec![0, 1], vec![0, 3], vec![1, 3]]);
+        assert!(selections.iter().all(|s| s.joltage() == Ok(99)));
+
+        // Picking the second 9 first leaves only 8 -> not optimal
+        let selections = find_all_max_batteries(&[9, 9, 8], 2);
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_render_selections() {
+        let puzzle = parse_puzzle("9198").unwrap();
+        let output = render_selections(&puzzle, 2, ShortBankPolicy::Error, None).unwrap();
+        assert_eq!(output, "\x1b[1;32m9\x1b[0m1\x1b[1;32m9\x1b[0m8 -> 99\n");
+
+        // 40 choose 20 ties, only the first ones are rendered
+        let puzzle = parse_puzzle(&"9".repeat(40)).unwrap();
+        let output = render_selections(&puzzle, 20, ShortBankPolicy::Error, Some(3)).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[3],
+            "... 137846528817 more optimal selections (137846528820 in total)"
+        );
+
+        let output = render_selections(
+            &parse_puzzle("9198").unwrap(),
+            2,
+            ShortBankPolicy::Error,
+            Some(3),
+        )
+        .unwrap();
+        assert_eq!(output.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_count_max_batteries() {
+        assert_eq!(count_max_batteries(&[9, 9, 8, 9], 2), BigUint::from(3u32));
+        assert_eq!(count_max_batteries(&[9, 9, 8], 2), BigUint::from(1u32));
+        assert_eq!(count_max_batteries(&[9], 2), BigUint::ZERO);
+        assert_eq!(
+            first_max_batteries(&[9, 9, 8, 9], 2, 2),
+            find_all_max_batteries(&[9, 9, 8, 9], 2)[..2]
+        );
+    }
+
+    #[test]
+    fn test_solve_puzzle_counts() {
+        let input = include_str!("../inputs/examples/day_3");
+        let puzzle = parse_puzzle(input).unwrap();
+        let counts = parse_battery_counts("2,12,15").unwrap();
+        let totals = solve_puzzle_counts(&puzzle, &counts, ShortBankPolicy::Error).unwrap();
+        assert_eq!(
+            totals,
+            vec![
+                BigUint::from(357u32),
+                BigUint::from(3121910778619u64),
+                // Whole banks
+                BigUint::from(
+                    987654321111111u64 + 811111111111119 + 234234234234278 + 818181911112111
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solve_puzzle_counts_wide() {
+        let puzzle = parse_puzzle(&"9".repeat(25)).unwrap();
+        let totals = solve_puzzle_counts(&puzzle, &[20], ShortBankPolicy::Error).unwrap();
+        assert_eq!(totals, vec!["9".repeat(20).parse::<BigUint>().unwrap()]);
+    }
+
+    #[test]
+    fn test_parse_battery_counts() {
+        assert_eq!(
+            parse_battery_counts("2, 5,12,20").unwrap(),
+            vec![2, 5, 12, 20]
+        );
+        assert!(parse_battery_counts("2,,5").is_err());
+    }
+
     #[test]
     fn test_parse_empty_puzzle() {
         let input = "";
@@ -169,8 +622,83 @@ mod tests {
     fn test_real_input() {
         let input = include_str!("../inputs/day_3");
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 17109);
         assert_eq!(solution.task_2, 169347417057382);
     }
+
+    /// Reference implementation: tries every combination of batteries.
+    /// Returns the best joltage and the positions of every combination reaching it.
+    fn max_batteries_naive(
+        batteries: &[usize],
+        num_on_batteries: usize,
+    ) -> (usize, Vec<Vec<usize>>) {
+        let mut best = 0;
+        let mut best_indices = Vec::new();
+
+        for mask in 0u32..1 << batteries.len() {
+            if mask.count_ones() as usize != num_on_batteries {
+                continue;
+            }
+
+            let indices: Vec<usize> = (0..batteries.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .collect();
+            let joltage = indices.iter().fold(0, |acc, &i| acc * 10 + batteries[i]);
+
+            if joltage > best {
+                best = joltage;
+                best_indices.clear();
+            }
+            if joltage == best {
+                best_indices.push(indices);
+            }
+        }
+
+        best_indices.sort();
+        (best, best_indices)
+    }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(
+            banks in prop::collection::vec(prop::collection::vec(1..=9usize, 1..=14), 1..4)
+        ) {
+            let input: Vec<String> = banks
+                .iter()
+                .map(|bank| bank.iter().map(|d| d.to_string()).collect())
+                .collect();
+            let puzzle = parse_puzzle(&input.join("\n")).unwrap();
+            let solution = solve_puzzle(puzzle).unwrap();
+
+            // Short banks turn on all their batteries
+            let expected = |num_on_batteries: usize| -> usize {
+                banks
+                    .iter()
+                    .map(|bank| max_batteries_naive(bank, num_on_batteries.min(bank.len())).0)
+                    .sum()
+            };
+            prop_assert_eq!(solution.task_1, expected(2));
+            prop_assert_eq!(solution.task_2, expected(12));
+        }
+
+        #[test]
+        fn test_find_all_max_batteries_matches_naive(
+            batteries in prop::collection::vec(1..=3usize, 1..=12),
+            num_on_batteries in 1..=12usize,
+        ) {
+            let (best, best_indices) = max_batteries_naive(&batteries, num_on_batteries);
+            let selections = find_all_max_batteries(&batteries, num_on_batteries);
+            prop_assert_eq!(count_max_batteries(&batteries, num_on_batteries), BigUint::from(selections.len()));
+            let mut indices: Vec<Vec<usize>> = selections.iter().map(|s| s.indices.clone()).collect();
+            indices.sort();
+
+            if num_on_batteries > batteries.len() {
+                prop_assert!(selections.is_empty());
+            } else {
+                prop_assert!(selections.iter().all(|s| s.joltage() == Ok(best)));
+                prop_assert_eq!(indices, best_indices);
+            }
+        }
+    }
 }
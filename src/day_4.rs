@@ -1,5 +1,6 @@
-use crate::common::Solution;
-use anyhow::{Result, anyhow};
+use crate::{common::Solution, grid_image::Grid};
+use anyhow::{Context, Result, anyhow};
+use std::{borrow::Cow, fs::File, path::Path, time::Duration};
 
 pub struct Puzzle {
     diagram: Vec<Vec<Position>>,
@@ -11,6 +12,15 @@ enum Position {
     Paper,
 }
 
+impl Position {
+    fn to_char(&self) -> char {
+        match self {
+            Position::Empty => '.',
+            Position::Paper => '@',
+        }
+    }
+}
+
 fn parse_position(c: char) -> Result<Position> {
     match c {
         '.' => Ok(Position::Empty),
@@ -22,6 +32,13 @@ fn parse_position(c: char) -> Result<Position> {
     }
 }
 
+impl Puzzle {
+    /// Length of the longest row
+    fn width(&self) -> usize {
+        self.diagram.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
 pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     let diagram = input
         .lines()
@@ -35,85 +52,401 @@ pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     Ok(Puzzle { diagram })
 }
 
-/// Pass `puzzle` as value and not reference to consume it (to avoid the caller reusing a modified puzzle afterwards).
-/// Returns (number of papers removed in first pass, number of papers removed in total)
-pub fn solve_puzzle(mut puzzle: Puzzle) -> Solution {
-    let mut removed_rounds: Vec<usize> = Vec::new();
+/// Shape of the positions considered adjacent to a paper
+#[derive(Clone, Debug, PartialEq)]
+pub enum Neighbourhood {
+    /// 4 orthogonal positions
+    VonNeumann,
+    /// 8 orthogonal and diagonal positions
+    Moore,
+    /// All positions in the square of radius `r` around the paper (`Radius(1)` is `Moore`)
+    Radius(usize),
+    /// Offsets (row, column) relative to the paper, (0, 0) and repeated offsets are ignored
+    Custom(Vec<(isize, isize)>),
+}
 
-    let mut done = false;
+impl Neighbourhood {
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let square = |r: isize| {
+            (-r..=r)
+                .flat_map(move |i| (-r..=r).map(move |j| (i, j)))
+                .collect::<Vec<(isize, isize)>>()
+        };
 
-    while !done {
-        let removed = remove_accessible_papers(&mut puzzle);
-        removed_rounds.push(removed);
-        done = removed == 0;
+        let offsets = match self {
+            Neighbourhood::VonNeumann => vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Neighbourhood::Moore => square(1),
+            Neighbourhood::Radius(r) => square(*r as isize),
+            Neighbourhood::Custom(offsets) => offsets.clone(),
+        };
+
+        // Ignore self and count each position once
+        let mut unique: Vec<(isize, isize)> = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            if offset != (0, 0) && !unique.contains(&offset) {
+                unique.push(offset);
+            }
+        }
+        unique
+    }
+}
+
+/// How positions outside of the diagram are treated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    /// Outside positions are empty
+    Empty,
+    /// Outside positions hold paper (that can never be removed)
+    Paper,
+    /// The diagram wraps around as a torus, as wide as its longest row (shorter rows are padded with empty positions)
+    Wrap,
+}
+
+/// Rule deciding if a paper is accessible: fewer than `threshold` papers in its `neighbourhood`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub threshold: usize,
+    pub neighbourhood: Neighbourhood,
+    pub edge: Edge,
+}
+
+impl Default for Rule {
+    /// Less than 4 papers in the 8 adjacent positions
+    fn default() -> Self {
+        Rule {
+            threshold: 4,
+            neighbourhood: Neighbourhood::Moore,
+            edge: Edge::Empty,
+        }
+    }
+}
+
+impl Rule {
+    fn is_accessible(&self, adjacent_papers: usize) -> bool {
+        adjacent_papers < self.threshold
     }
+}
+
+/// Pass `puzzle` as value and not reference to consume it (to avoid the caller reusing a modified puzzle afterwards).
+/// Returns (number of papers removed in first pass, number of papers removed in total)
+pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
+    solve_puzzle_with_rule(puzzle, &Rule::default())
+}
 
-    let total_removed = removed_rounds.iter().sum();
-    let first_pass = removed_rounds.first().unwrap_or(&0);
+/// Same as [`solve_puzzle`], with papers accessible according to `rule`
+pub fn solve_puzzle_with_rule(mut puzzle: Puzzle, rule: &Rule) -> Solution {
+    let removed_rounds = remove_papers_by_round(&mut puzzle, rule);
+
+    let total_removed = removed_rounds.iter().map(Vec::len).sum();
+    let first_pass = removed_rounds.first().map(Vec::len).unwrap_or(0);
 
     Solution {
-        task_1: *first_pass,
+        task_1: first_pass,
         task_2: total_removed,
     }
 }
 
-/// Returns the number of papers removed
-fn remove_accessible_papers(puzzle: &mut Puzzle) -> usize {
-    let mut positions_to_remove: Vec<(usize, usize)> = Vec::new();
+/// Papers removed in each round, to replay the removal
+pub struct RemovalHistory {
+    initial_diagram: Vec<Vec<Position>>,
+    /// Positions removed in each round (in reading order), only rounds removing papers
+    rounds: Vec<Vec<(usize, usize)>>,
+}
 
-    for (x, row) in puzzle.diagram.iter().enumerate() {
-        for (y, pos) in row.iter().enumerate() {
-            // If pos is accessible paper, mark it as 'to be removed'
-            if pos == &Position::Paper && is_accessible(puzzle, x, y) {
-                positions_to_remove.push((x, y));
+pub fn removal_history(mut puzzle: Puzzle, rule: &Rule) -> RemovalHistory {
+    let initial_diagram = puzzle.diagram.clone();
+
+    let mut rounds = remove_papers_by_round(&mut puzzle, rule);
+    // Drop the final round, it does not remove anything
+    rounds.retain(|round| !round.is_empty());
+    // Positions in reading order
+    rounds.iter_mut().for_each(|round| round.sort());
+
+    RemovalHistory {
+        initial_diagram,
+        rounds,
+    }
+}
+
+impl RemovalHistory {
+    pub fn rounds(&self) -> &[Vec<(usize, usize)>] {
+        &self.rounds
+    }
+
+    /// Round (starting at 1) in which each position was removed, `None` if it was never removed
+    pub fn removal_rounds(&self) -> Vec<Vec<Option<usize>>> {
+        let mut removal_rounds: Vec<Vec<Option<usize>>> = self
+            .initial_diagram
+            .iter()
+            .map(|row| vec![None; row.len()])
+            .collect();
+
+        for (round, positions) in self.rounds.iter().enumerate() {
+            for &(x, y) in positions {
+                removal_rounds[x][y] = Some(round + 1);
             }
         }
+
+        removal_rounds
     }
 
-    // Remove positions
-    // Note: Can't do in 1 loop as otherwise the borrow checker complains because
-    // there is an immutable borrow (to call `is_accessible`) inside the mutable borrow of the loop
-    for (x, y) in positions_to_remove.iter() {
-        puzzle.diagram[*x][*y] = Position::Empty;
+    /// Initial diagram, with the removal round of each paper as value (to export as image)
+    pub fn grid(&self) -> Grid {
+        let cells = self
+            .initial_diagram
+            .iter()
+            .map(|row| row.iter().map(Position::to_char).collect())
+            .collect();
+
+        Grid {
+            cells,
+            values: self.removal_rounds(),
+        }
+    }
+
+    /// Diagram after `round` rounds (0 is the initial diagram).
+    /// Removed papers are shown as '.' in the colour of the round they were removed in.
+    pub fn render_frame(&self, round: usize) -> String {
+        let removal_rounds = self.removal_rounds();
+        let mut frame = String::new();
+
+        for (x, row) in self.initial_diagram.iter().enumerate() {
+            for (y, pos) in row.iter().enumerate() {
+                match (pos, removal_rounds[x][y]) {
+                    (Position::Paper, Some(removed)) if removed <= round => {
+                        let (r, g, b) = round_colour(removed, self.rounds.len());
+                        frame.push_str(&format!("\x1b[38;2;{};{};{}m.\x1b[0m", r, g, b));
+                    }
+                    (Position::Paper, _) => frame.push('@'),
+                    (Position::Empty, _) => frame.push('.'),
+                }
+            }
+            frame.push('\n');
+        }
+
+        frame
     }
 
-    positions_to_remove.len()
+    /// Prints every frame in the terminal, waiting `delay` between frames
+    pub fn animate(&self, delay: Duration) {
+        for round in 0..=self.rounds.len() {
+            // Clear screen and move cursor to top left
+            print!("\x1b[2J\x1b[H");
+            println!("Round {}/{}", round, self.rounds.len());
+            print!("{}", self.render_frame(round));
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Writes the animation as a looping GIF, each cell being a `scale` x `scale` square
+    pub fn write_gif(&self, path: &Path, scale: usize, delay: Duration) -> Result<()> {
+        const EMPTY: u8 = 0;
+        const PAPER: u8 = 1;
+        // GIF palettes hold 256 colours, the 2 first are for empty and paper
+        const NUM_ROUND_COLOURS: usize = 254;
+
+        let height = self.initial_diagram.len();
+        let width = self.initial_diagram.iter().map(Vec::len).max().unwrap_or(0);
+
+        let image_width = u16::try_from(width * scale).context("Image is too wide for GIF")?;
+        let image_height = u16::try_from(height * scale).context("Image is too high for GIF")?;
+        // In hundredths of a second
+        let frame_delay = u16::try_from(delay.as_millis() / 10)
+            .context("Delay is too long for GIF, at most 655350ms")?;
+        if frame_delay == 0 {
+            return Err(anyhow!("Delay is too short for GIF, at least 10ms"));
+        }
+
+        let num_colours = self.rounds.len().min(NUM_ROUND_COLOURS);
+        let mut palette: Vec<u8> = vec![0, 0, 0, 200, 200, 200];
+        for colour in 1..=num_colours {
+            let (r, g, b) = round_colour(colour, num_colours);
+            palette.extend([r, g, b]);
+        }
+        // Rounds share colours if there are more rounds than colours
+        let colour_index = |round: usize| (2 + (round - 1) * num_colours / self.rounds.len()) as u8;
+
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create file {}", path.display()))?;
+        let mut encoder = gif::Encoder::new(file, image_width, image_height, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        let removal_rounds = self.removal_rounds();
+        let mut pixels = vec![EMPTY; width * scale * height * scale];
+
+        for round in 0..=self.rounds.len() {
+            for (x, row) in self.initial_diagram.iter().enumerate() {
+                for (y, pos) in row.iter().enumerate() {
+                    let index = match (pos, removal_rounds[x][y]) {
+                        (Position::Paper, Some(removed)) if removed <= round => {
+                            colour_index(removed)
+                        }
+                        (Position::Paper, _) => PAPER,
+                        (Position::Empty, _) => EMPTY,
+                    };
+
+                    for i in x * scale..(x + 1) * scale {
+                        pixels[i * width * scale + y * scale..][..scale].fill(index);
+                    }
+                }
+            }
+
+            let frame = gif::Frame {
+                width: image_width,
+                height: image_height,
+                delay: frame_delay,
+                buffer: Cow::Borrowed(&pixels),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Colour of papers removed in `round` (1..=`num_rounds`), from yellow for the first round to purple for the last
+fn round_colour(round: usize, num_rounds: usize) -> (u8, u8, u8) {
+    let t = if num_rounds <= 1 {
+        0.0
+    } else {
+        (round - 1) as f64 / (num_rounds - 1) as f64
+    };
+
+    let lerp = |from: f64, to: f64| (from + (to - from) * t).round() as u8;
+
+    (lerp(255.0, 120.0), lerp(220.0, 0.0), lerp(0.0, 160.0))
 }
 
-/// Less than 4 papers in adjacent positions
-fn is_accessible(puzzle: &Puzzle, x: usize, y: usize) -> bool {
-    let mut adjacent_papers = 0;
+/// Removes accessible papers round by round until none is accessible.
+/// Returns the positions removed in each round (the last round removes nothing).
+///
+/// Instead of rescanning the whole diagram each round, the number of adjacent papers is kept for every position
+/// and only the neighbours of the papers removed in a round are examined in the next one (nothing else changed).
+fn remove_papers_by_round(puzzle: &mut Puzzle, rule: &Rule) -> Vec<Vec<(usize, usize)>> {
+    let offsets = rule.neighbourhood.offsets();
+    // Positions having (x, y) as neighbour, differs from `offsets` for asymmetric custom neighbourhoods
+    let reverse_offsets: Vec<(isize, isize)> = offsets.iter().map(|&(i, j)| (-i, -j)).collect();
+    let width = puzzle.width();
 
-    let x_i32 = x as i32;
-    let y_i32 = y as i32;
+    let mut adjacent_papers: Vec<Vec<usize>> = puzzle
+        .diagram
+        .iter()
+        .enumerate()
+        .map(|(x, row)| {
+            (0..row.len())
+                .map(|y| count_adjacent_papers(&puzzle.diagram, width, x, y, &offsets, rule.edge))
+                .collect()
+        })
+        .collect();
 
-    for i in -1..=1 {
-        for j in -1..=1 {
-            if i == 0 && j == 0 {
-                // Ignore self
-                continue;
+    // Papers to examine in the current round: all of them in the first round
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    for (x, row) in puzzle.diagram.iter().enumerate() {
+        for (y, pos) in row.iter().enumerate() {
+            if pos == &Position::Paper {
+                candidates.push((x, y));
             }
+        }
+    }
+
+    // Last round in which a position was added to candidates, to avoid adding it twice
+    let mut queued_round: Vec<Vec<usize>> = puzzle
+        .diagram
+        .iter()
+        .map(|row| vec![0; row.len()])
+        .collect();
+
+    let mut removed_rounds: Vec<Vec<(usize, usize)>> = Vec::new();
+
+    for round in 1.. {
+        // Removed papers are decided before any removal, as if all were removed at the same time
+        let removed: Vec<(usize, usize)> = candidates
+            .drain(..)
+            .filter(|&(x, y)| {
+                puzzle.diagram[x][y] == Position::Paper && rule.is_accessible(adjacent_papers[x][y])
+            })
+            .collect();
+
+        if removed.is_empty() {
+            removed_rounds.push(removed);
+            break;
+        }
+
+        for &(x, y) in &removed {
+            puzzle.diagram[x][y] = Position::Empty;
+        }
 
-            let x = x_i32 + i;
-            let y = y_i32 + j;
+        for &(x, y) in &removed {
+            for &offset in &reverse_offsets {
+                let Some((i, j)) = shift(&puzzle.diagram, width, x, y, offset, rule.edge) else {
+                    continue;
+                };
 
-            if x >= 0
-                && let Some(row) = puzzle.diagram.get(x as usize)
-                && y >= 0
-                && let Some(pos) = row.get(y as usize)
-                && pos == &Position::Paper
-            {
-                adjacent_papers += 1;
+                adjacent_papers[i][j] -= 1;
+
+                if puzzle.diagram[i][j] == Position::Paper && queued_round[i][j] != round {
+                    queued_round[i][j] = round;
+                    candidates.push((i, j));
+                }
             }
         }
+
+        removed_rounds.push(removed);
     }
 
-    adjacent_papers < 4
+    removed_rounds
+}
+
+/// Number of papers at `offsets` from (x, y), including outside positions if `edge` is `Edge::Paper`
+fn count_adjacent_papers(
+    diagram: &[Vec<Position>],
+    width: usize,
+    x: usize,
+    y: usize,
+    offsets: &[(isize, isize)],
+    edge: Edge,
+) -> usize {
+    offsets
+        .iter()
+        .filter(|&&offset| match shift(diagram, width, x, y, offset, edge) {
+            Some((i, j)) => diagram[i][j] == Position::Paper,
+            None => edge == Edge::Paper,
+        })
+        .count()
+}
+
+/// Position at `offset` from (x, y), or `None` if it is outside of the diagram.
+/// With `Edge::Wrap`, the diagram wraps around as a torus `width` positions wide: positions past the end of a
+/// shorter row are outside, so that shifting back by the opposite offset always returns to (x, y).
+fn shift(
+    diagram: &[Vec<Position>],
+    width: usize,
+    x: usize,
+    y: usize,
+    (i, j): (isize, isize),
+    edge: Edge,
+) -> Option<(usize, usize)> {
+    let x = x as isize + i;
+    let y = y as isize + j;
+
+    let (x, y) = if edge == Edge::Wrap {
+        (
+            x.rem_euclid(diagram.len() as isize) as usize,
+            y.rem_euclid(width.max(1) as isize) as usize,
+        )
+    } else {
+        (usize::try_from(x).ok()?, usize::try_from(y).ok()?)
+    };
+
+    diagram.get(x)?.get(y)?;
+    Some((x, y))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_example_puzzle() {
@@ -138,6 +471,243 @@ mod tests {
         assert_eq!(solution.task_2, 4);
     }
 
+    /// Reference implementation: rescans the whole diagram every round
+    fn remove_papers_by_round_naive(puzzle: &mut Puzzle, rule: &Rule) -> Vec<Vec<(usize, usize)>> {
+        let offsets = rule.neighbourhood.offsets();
+        let width = puzzle.width();
+        let mut removed_rounds = Vec::new();
+
+        loop {
+            let mut positions_to_remove: Vec<(usize, usize)> = Vec::new();
+
+            for (x, row) in puzzle.diagram.iter().enumerate() {
+                for (y, pos) in row.iter().enumerate() {
+                    let adjacent_papers =
+                        count_adjacent_papers(&puzzle.diagram, width, x, y, &offsets, rule.edge);
+                    if pos == &Position::Paper && rule.is_accessible(adjacent_papers) {
+                        positions_to_remove.push((x, y));
+                    }
+                }
+            }
+
+            for (x, y) in positions_to_remove.iter() {
+                puzzle.diagram[*x][*y] = Position::Empty;
+            }
+
+            let done = positions_to_remove.is_empty();
+            removed_rounds.push(positions_to_remove);
+
+            if done {
+                return removed_rounds;
+            }
+        }
+    }
+
+    /// Naive removal finds positions in row order
+    fn sorted(mut rounds: Vec<Vec<(usize, usize)>>) -> Vec<Vec<(usize, usize)>> {
+        rounds.iter_mut().for_each(|round| round.sort());
+        rounds
+    }
+
+    #[test]
+    fn test_rounds_match_naive() {
+        let rules = [
+            Rule::default(),
+            Rule {
+                threshold: 3,
+                neighbourhood: Neighbourhood::VonNeumann,
+                edge: Edge::Paper,
+            },
+            Rule {
+                threshold: 10,
+                neighbourhood: Neighbourhood::Radius(2),
+                edge: Edge::Wrap,
+            },
+            Rule {
+                threshold: 2,
+                neighbourhood: Neighbourhood::Custom(vec![(0, 1), (0, 2), (1, 1)]),
+                edge: Edge::Empty,
+            },
+        ];
+
+        for rule in &rules {
+            let input = include_str!("../inputs/examples/day_4");
+            let mut puzzle = parse_puzzle(input).unwrap();
+            let mut naive_puzzle = parse_puzzle(input).unwrap();
+            assert_eq!(
+                sorted(remove_papers_by_round(&mut puzzle, rule)),
+                remove_papers_by_round_naive(&mut naive_puzzle, rule)
+            );
+            assert!(puzzle.diagram == naive_puzzle.diagram);
+        }
+
+        let input = include_str!("../inputs/day_4");
+        let mut puzzle = parse_puzzle(input).unwrap();
+        let mut naive_puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(
+            sorted(remove_papers_by_round(&mut puzzle, &Rule::default())),
+            remove_papers_by_round_naive(&mut naive_puzzle, &Rule::default())
+        );
+
+        // Ragged rows wrap against the longest one
+        let rule = Rule {
+            threshold: 8,
+            neighbourhood: Neighbourhood::Moore,
+            edge: Edge::Wrap,
+        };
+        let input = "@\n.\n@@@";
+        let mut puzzle = parse_puzzle(input).unwrap();
+        let mut naive_puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(
+            sorted(remove_papers_by_round(&mut puzzle, &rule)),
+            remove_papers_by_round_naive(&mut naive_puzzle, &rule)
+        );
+        assert_eq!(
+            removal_history(parse_puzzle(input).unwrap(), &rule)
+                .rounds()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_edge_rules() {
+        let input = "
+@@@
+@@@
+@@@
+        "
+        .trim();
+
+        // Corners have 3 neighbours, edges 5, center 8
+        let puzzle = parse_puzzle(input).unwrap();
+        let solution = solve_puzzle_with_rule(puzzle, &Rule::default());
+        assert_eq!(solution.task_1, 4);
+        assert_eq!(solution.task_2, 9);
+
+        // Everything has 8 neighbours
+        for edge in [Edge::Paper, Edge::Wrap] {
+            let rule = Rule {
+                edge,
+                ..Rule::default()
+            };
+            let puzzle = parse_puzzle(input).unwrap();
+            let solution = solve_puzzle_with_rule(puzzle, &rule);
+            assert_eq!(solution.task_1, 0);
+            assert_eq!(solution.task_2, 0);
+        }
+
+        // Corners have 2 neighbours, edges 3, center 4
+        let rule = Rule {
+            threshold: 3,
+            neighbourhood: Neighbourhood::VonNeumann,
+            edge: Edge::Empty,
+        };
+        let puzzle = parse_puzzle(input).unwrap();
+        let solution = solve_puzzle_with_rule(puzzle, &rule);
+        assert_eq!(solution.task_1, 4);
+        assert_eq!(solution.task_2, 9);
+    }
+
+    #[test]
+    fn test_custom_neighbourhood() {
+        let neighbourhood = Neighbourhood::Custom(vec![(0, 1), (0, 0), (0, 1), (1, 0)]);
+        assert_eq!(neighbourhood.offsets(), vec![(0, 1), (1, 0)]);
+
+        // The right neighbour counts once: 1 < 2 is accessible
+        let rule = Rule {
+            threshold: 2,
+            neighbourhood: Neighbourhood::Custom(vec![(0, 1), (0, 1)]),
+            edge: Edge::Empty,
+        };
+        let puzzle = parse_puzzle("@@").unwrap();
+        let solution = solve_puzzle_with_rule(puzzle, &rule);
+        assert_eq!(solution.task_1, 2);
+    }
+
+    #[test]
+    fn test_removal_history() {
+        let input = "
+@@@
+@@@
+@@@
+        "
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+        let history = removal_history(puzzle, &Rule::default());
+
+        assert_eq!(
+            history.rounds(),
+            &[
+                vec![(0, 0), (0, 2), (2, 0), (2, 2)],
+                vec![(0, 1), (1, 0), (1, 2), (2, 1)],
+                vec![(1, 1)],
+            ]
+        );
+        assert_eq!(
+            history.removal_rounds(),
+            vec![
+                vec![Some(1), Some(2), Some(1)],
+                vec![Some(2), Some(3), Some(2)],
+                vec![Some(1), Some(2), Some(1)],
+            ]
+        );
+
+        assert_eq!(history.render_frame(0), format!("{}\n", input));
+        let first = "\x1b[38;2;255;220;0m.\x1b[0m";
+        assert_eq!(
+            history.render_frame(1),
+            format!("{first}@{first}\n@@@\n{first}@{first}\n")
+        );
+    }
+
+    #[test]
+    fn test_removal_grid() {
+        let puzzle = parse_puzzle(".@@\n@@.").unwrap();
+        let grid = removal_history(puzzle, &Rule::default()).grid();
+        assert_eq!(grid.cells, vec![vec!['.', '@', '@'], vec!['@', '@', '.']]);
+        assert_eq!(
+            grid.values,
+            vec![vec![None, Some(1), Some(1)], vec![Some(1), Some(1), None]]
+        );
+    }
+
+    #[test]
+    fn test_write_gif() {
+        let input = include_str!("../inputs/examples/day_4");
+        let puzzle = parse_puzzle(input).unwrap();
+        let history = removal_history(puzzle, &Rule::default());
+
+        // Unique per process, for parallel test runs
+        let path =
+            std::env::temp_dir().join(format!("aoc_2025_day_4_test_{}.gif", std::process::id()));
+        history
+            .write_gif(&path, 2, Duration::from_millis(100))
+            .unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        assert!(content.starts_with(b"GIF89a"));
+        std::fs::remove_file(&path).unwrap();
+
+        let error = history
+            .write_gif(&path, 2, Duration::from_millis(655360))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Delay is too long for GIF, at most 655350ms"
+        );
+        assert!(!path.exists());
+
+        let error = history
+            .write_gif(&path, 2, Duration::from_millis(9))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Delay is too short for GIF, at least 10ms"
+        );
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_real_input() {
         let input = include_str!("../inputs/day_4");
@@ -146,4 +716,51 @@ mod tests {
         assert_eq!(solution.task_1, 1349);
         assert_eq!(solution.task_2, 8277);
     }
+
+    fn diagram() -> impl Strategy<Value = String> {
+        (1..8usize, 1..8usize).prop_flat_map(|(height, width)| {
+            prop::collection::vec(prop::collection::vec(any::<bool>(), width), height).prop_map(
+                |rows| {
+                    let rows: Vec<String> = rows
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|&paper| if paper { '@' } else { '.' })
+                                .collect()
+                        })
+                        .collect();
+                    rows.join("\n")
+                },
+            )
+        })
+    }
+
+    fn rule() -> impl Strategy<Value = Rule> {
+        let neighbourhood = prop_oneof![
+            Just(Neighbourhood::VonNeumann),
+            Just(Neighbourhood::Moore),
+            (1..3usize).prop_map(Neighbourhood::Radius),
+            prop::collection::vec((-2..=2isize, -2..=2isize), 0..5).prop_map(Neighbourhood::Custom),
+        ];
+        let edge = prop_oneof![Just(Edge::Empty), Just(Edge::Paper), Just(Edge::Wrap)];
+
+        (1..10usize, neighbourhood, edge).prop_map(|(threshold, neighbourhood, edge)| Rule {
+            threshold,
+            neighbourhood,
+            edge,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_rounds_match_naive_random(input in diagram(), rule in rule()) {
+            let mut puzzle = parse_puzzle(&input).unwrap();
+            let mut naive_puzzle = parse_puzzle(&input).unwrap();
+            prop_assert_eq!(
+                sorted(remove_papers_by_round(&mut puzzle, &rule)),
+                remove_papers_by_round_naive(&mut naive_puzzle, &rule)
+            );
+            prop_assert!(puzzle.diagram == naive_puzzle.diagram);
+        }
+    }
 }
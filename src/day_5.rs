@@ -1,33 +1,85 @@
-use crate::{common::Solution, range::Range, range::merge_ranges};
-use anyhow::{Context, Result};
+use crate::{
+    common::Solution,
+    range::{Range, RangeSet},
+};
+use anyhow::{Result, anyhow};
+use std::fmt::Display;
 
 pub struct Puzzle {
     fresh_ingredients: Vec<Range>,
     available_ingredients: Vec<usize>,
 }
 
+/// Input is made of 2 sections separated by a single blank line: fresh ingredient ranges, then available ingredients.
+/// Errors report line numbers (starting at 1).
 pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
-    let mut reading_fresh_ingredients = true;
-
-    let mut fresh_ingredients = Vec::new();
-    let mut available_ingredients = Vec::new();
-
-    for line in input.lines() {
-        if line.is_empty() {
-            // Empty line, we are now reading the available ingredients
-            reading_fresh_ingredients = false;
-        } else if reading_fresh_ingredients {
-            let range = Range::parse(line)?;
-            fresh_ingredients.push(range);
-        } else {
-            let id: usize = line
-                .parse()
-                .with_context(|| format!("Failed to parse available ingredient: '{}'", line))?;
-
-            available_ingredients.push(id);
-        }
+    let lines: Vec<&str> = input.lines().collect();
+
+    let Some(separator) = lines.iter().position(|line| line.trim().is_empty()) else {
+        return Err(anyhow!(
+            "Missing blank line separating fresh ingredient ranges from available ingredients"
+        ));
+    };
+
+    if separator == 0 {
+        return Err(anyhow!(
+            "Missing fresh ingredient ranges section: line 1 is blank"
+        ));
+    }
+
+    // Blank lines at the end of the file are not separators
+    let num_lines = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let lines = &lines[..num_lines];
+
+    if separator >= lines.len() {
+        return Err(anyhow!(
+            "Missing available ingredients section after blank line {}",
+            separator + 1
+        ));
     }
 
+    let fresh_ingredients = lines[..separator]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| Range::parse(line).map_err(|e| anyhow!("Line {}: {:#}", i + 1, e)))
+        .collect::<Result<Vec<Range>>>()?;
+
+    let available_ingredients = lines[separator + 1..]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line_number = separator + i + 2;
+
+            if line.trim().is_empty() {
+                return Err(anyhow!(
+                    "Line {}: unexpected blank line, expected a single blank line between sections (found at line {})",
+                    line_number,
+                    separator + 1
+                ));
+            }
+
+            if Range::<usize>::parse(line).is_ok() {
+                return Err(anyhow!(
+                    "Line {}: found range '{}' in available ingredients section",
+                    line_number,
+                    line
+                ));
+            }
+
+            line.parse().map_err(|e| {
+                anyhow!(
+                    "Line {}: failed to parse available ingredient: '{}': {}",
+                    line_number,
+                    line,
+                    e
+                )
+            })
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
     Ok(Puzzle {
         fresh_ingredients,
         available_ingredients,
@@ -36,31 +88,123 @@ pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
 
 pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
     // Merge ranges to have non-overlapping ranges
-    let fresh_ingredients = merge_ranges(puzzle.fresh_ingredients);
+    let fresh_ingredients = RangeSet::from_ranges(puzzle.fresh_ingredients);
 
-    // Count number of available ingredients that are fresh
-    let task_1 = puzzle
-        .available_ingredients
+    let task_1 = count_fresh(&fresh_ingredients, &puzzle.available_ingredients);
+
+    // Number of fresh ingredients (ranges do not overlap)
+    // Can only saturate if all usize ids are fresh
+    let task_2 = usize::try_from(fresh_ingredients.count()).unwrap_or(usize::MAX);
+
+    Solution { task_1, task_2 }
+}
+
+/// Count number of available ingredients that are fresh.
+/// Sweeps through ranges if ingredients are sorted, otherwise does a binary search per ingredient.
+pub fn count_fresh(fresh_ingredients: &RangeSet, available_ingredients: &[usize]) -> usize {
+    if available_ingredients.is_sorted() {
+        fresh_ingredients.count_contained_sorted(available_ingredients)
+    } else {
+        available_ingredients
+            .iter()
+            .filter(|id| fresh_ingredients.contains(**id))
+            .count()
+    }
+}
+
+/// Why an ingredient is fresh or not
+#[derive(Debug, PartialEq)]
+pub struct Explanation {
+    pub id: usize,
+    /// Ranges from the input containing the ingredient, with their index in the input
+    pub original_ranges: Vec<(usize, Range)>,
+    /// Merged range containing the ingredient, `None` if it is spoiled
+    pub merged_range: Option<Range>,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(merged_range) = &self.merged_range else {
+            return write!(f, "Ingredient {} is spoiled", self.id);
+        };
+
+        write!(
+            f,
+            "Ingredient {} is fresh, in merged range {} from",
+            self.id, merged_range
+        )?;
+        for (index, range) in &self.original_ranges {
+            write!(f, "\n- range #{} {}", index + 1, range)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn explain(puzzle: &Puzzle, id: usize) -> Explanation {
+    let original_ranges: Vec<(usize, Range)> = puzzle
+        .fresh_ingredients
         .iter()
-        .filter(|id| is_fresh(&fresh_ingredients, **id))
-        .count();
+        .enumerate()
+        .filter(|(_, range)| range.is_included(id))
+        .map(|(index, range)| (index, range.clone()))
+        .collect();
 
-    // Compute number of fresh ingredients assuming ranges do not overlap
-    let mut task_2 = 0;
-    for range in fresh_ingredients {
-        task_2 += range.count();
+    let fresh_ingredients = RangeSet::from_ranges(puzzle.fresh_ingredients.clone());
+    let merged_range = fresh_ingredients
+        .nearest(id)
+        .filter(|(_, distance)| *distance == 0)
+        .map(|(range, _)| range.clone());
+
+    Explanation {
+        id,
+        original_ranges,
+        merged_range,
     }
+}
 
-    Solution { task_1, task_2 }
+/// Spoiled available ingredient, with the closest fresh range
+#[derive(Debug, PartialEq)]
+pub struct Spoiled {
+    pub id: usize,
+    /// Closest merged range and its distance to the ingredient, `None` if there are no fresh ranges
+    pub nearest: Option<(Range, u128)>,
 }
 
-fn is_fresh(fresh_ingredients: &[Range], id: usize) -> bool {
-    fresh_ingredients.iter().any(|range| range.is_included(id))
+impl Display for Spoiled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.nearest {
+            Some((range, distance)) => write!(
+                f,
+                "{}: nearest fresh range {} ({} away)",
+                self.id, range, distance
+            ),
+            None => write!(f, "{}: no fresh range", self.id),
+        }
+    }
+}
+
+/// Spoiled available ingredients, in input order
+pub fn spoiled_report(puzzle: &Puzzle) -> Vec<Spoiled> {
+    let fresh_ingredients = RangeSet::from_ranges(puzzle.fresh_ingredients.clone());
+
+    puzzle
+        .available_ingredients
+        .iter()
+        .filter(|&&id| !fresh_ingredients.contains(id))
+        .map(|&id| Spoiled {
+            id,
+            nearest: fresh_ingredients
+                .nearest(id)
+                .map(|(range, distance)| (range.clone(), distance)),
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
 
     #[test]
     fn test_example_puzzle() {
@@ -71,6 +215,136 @@ mod tests {
         assert_eq!(solution.task_2, 14);
     }
 
+    fn parse_error(input: &str) -> String {
+        match parse_puzzle(input) {
+            Ok(_) => panic!("Expected error for input {:?}", input),
+            // Without the error chain, as printed by the CLI
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_trailing_blank_lines() {
+        let puzzle = parse_puzzle("3-5\n\n4\n\n\n").unwrap();
+        assert_eq!(puzzle.fresh_ingredients, vec![Range::new(3, 5).unwrap()]);
+        assert_eq!(puzzle.available_ingredients, vec![4]);
+    }
+
+    #[test]
+    fn test_parse_missing_sections() {
+        assert_eq!(
+            parse_error(""),
+            "Missing blank line separating fresh ingredient ranges from available ingredients"
+        );
+        assert_eq!(
+            parse_error("3-5\n10-14"),
+            "Missing blank line separating fresh ingredient ranges from available ingredients"
+        );
+        assert_eq!(
+            parse_error("\n4"),
+            "Missing fresh ingredient ranges section: line 1 is blank"
+        );
+        assert_eq!(
+            parse_error("3-5\n\n"),
+            "Missing available ingredients section after blank line 2"
+        );
+        assert_eq!(
+            parse_error("3-5\n10-14\n \n\n"),
+            "Missing available ingredients section after blank line 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_extra_separator() {
+        assert_eq!(
+            parse_error("3-5\n\n10-14\n\n4"),
+            "Line 3: found range '10-14' in available ingredients section"
+        );
+        assert_eq!(
+            parse_error("3-5\n\n\n4"),
+            "Line 3: unexpected blank line, expected a single blank line between sections (found at line 2)"
+        );
+        assert_eq!(
+            parse_error("3-5\n\n4\n\n5"),
+            "Line 4: unexpected blank line, expected a single blank line between sections (found at line 2)"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_lines() {
+        assert_eq!(
+            parse_error("3-5\n7\n\n4"),
+            "Line 2: Invalid range format, expected x-y, found '7'"
+        );
+        assert_eq!(
+            parse_error("3-5\n\n4\nx"),
+            "Line 4: failed to parse available ingredient: 'x': invalid digit found in string"
+        );
+        assert_eq!(
+            parse_error("3-5\n[4,2)\n\n4"),
+            "Line 2: Cannot create interval where end < start: '[4,2)'"
+        );
+        assert_eq!(
+            parse_error("3-5\n4..2\n\n4"),
+            "Line 2: Invalid range: '4..2': Cannot create half-open range where end < start: [4, 2)"
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let input = include_str!("../inputs/examples/day_5");
+        let puzzle = parse_puzzle(input).unwrap();
+
+        let explanation = explain(&puzzle, 17);
+        assert_eq!(
+            explanation.original_ranges,
+            vec![
+                (2, Range::new(16, 20).unwrap()),
+                (3, Range::new(12, 18).unwrap())
+            ]
+        );
+        assert_eq!(explanation.merged_range, Some(Range::new(10, 20).unwrap()));
+        assert_eq!(
+            explanation.to_string(),
+            "Ingredient 17 is fresh, in merged range [10, 20] from\n- range #3 [16, 20]\n- range #4 [12, 18]"
+        );
+
+        let explanation = explain(&puzzle, 8);
+        assert!(explanation.original_ranges.is_empty());
+        assert_eq!(explanation.merged_range, None);
+        assert_eq!(explanation.to_string(), "Ingredient 8 is spoiled");
+    }
+
+    #[test]
+    fn test_spoiled_report() {
+        let input = include_str!("../inputs/examples/day_5");
+        let puzzle = parse_puzzle(input).unwrap();
+        let report = spoiled_report(&puzzle);
+        let lines: Vec<String> = report.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "1: nearest fresh range [3, 5] (2 away)",
+                "8: nearest fresh range [10, 20] (2 away)",
+                "32: nearest fresh range [10, 20] (12 away)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_fresh_sorted() {
+        let input = include_str!("../inputs/day_5");
+        let puzzle = parse_puzzle(input).unwrap();
+        let fresh_ingredients = RangeSet::from_ranges(puzzle.fresh_ingredients);
+
+        let mut available_ingredients = puzzle.available_ingredients;
+        let unsorted = count_fresh(&fresh_ingredients, &available_ingredients);
+        available_ingredients.sort();
+        let sorted = count_fresh(&fresh_ingredients, &available_ingredients);
+        assert_eq!(unsorted, 577);
+        assert_eq!(sorted, 577);
+    }
+
     #[test]
     fn test_real_input() {
         let input = include_str!("../inputs/day_5");
@@ -79,4 +353,29 @@ mod tests {
         assert_eq!(solution.task_1, 577);
         assert_eq!(solution.task_2, 350513176552950);
     }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(
+            ranges in prop::collection::vec((0..1_000usize, 0..100usize), 1..8),
+            available_ingredients in prop::collection::vec(0..1_200usize, 1..20),
+        ) {
+            let ranges: Vec<(usize, usize)> = ranges
+                .into_iter()
+                .map(|(start, length)| (start, start + length))
+                .collect();
+
+            let mut input: Vec<String> = ranges.iter().map(|(start, end)| format!("{}-{}", start, end)).collect();
+            input.push(String::new());
+            input.extend(available_ingredients.iter().map(|id| id.to_string()));
+            let puzzle = parse_puzzle(&input.join("\n")).unwrap();
+            let solution = solve_puzzle(puzzle);
+
+            // Reference implementation: every fresh id in a set
+            let fresh: HashSet<usize> = ranges.iter().flat_map(|&(start, end)| start..=end).collect();
+            let expected_fresh = available_ingredients.iter().filter(|id| fresh.contains(id)).count();
+            prop_assert_eq!(solution.task_1, expected_fresh);
+            prop_assert_eq!(solution.task_2, fresh.len());
+        }
+    }
 }
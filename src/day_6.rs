@@ -1,28 +1,58 @@
 use crate::common::Solution;
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use std::fmt::Display;
 
 #[derive(Debug)]
 pub struct Puzzle {
     problems: Vec<Problem>,
 }
 
-/// Operators (addition and multiplication) are commutative, ignore the right to left columns and problems for task 2.
-/// If we still want to do it, only need to reverse the problems and their columns for task 2.
+/// Operands are stored in reading order: rows from top to bottom for task 1, columns from right to left for task 2.
+/// The order matters for non-commutative operators (subtraction, division, exponent).
 #[derive(Debug)]
 struct Problem {
     rows: Vec<usize>,
     columns: Vec<usize>,
     operator: Operator,
+    /// Results given by the worksheet for each reading
+    expected_rows: Option<i128>,
+    expected_columns: Option<i128>,
 }
 
 impl Problem {
-    fn new(columns_numbers: &[Vec<Number>], operator: Operator) -> Result<Problem> {
-        let columns: Vec<usize> = Self::vec_numbers_to_usize(columns_numbers)?;
-        let rows: Vec<usize> = Self::vec_numbers_to_usize(&transpose(columns_numbers)?)?;
+    /// `columns_numbers` are given from left to right, the first one being at `first_column` in `lines`
+    fn new(
+        columns_numbers: &[Vec<Number>],
+        lines: &[Line],
+        operator: Operator,
+        first_column: usize,
+    ) -> Result<Problem> {
+        let mut columns: Vec<usize> = Self::vec_numbers_to_usize(columns_numbers)
+            .map_err(|e| anyhow!("Problem at column {}: {:#}", first_column + 1, e))?;
+        columns.reverse();
+        let rows: Vec<usize> = transpose(columns_numbers)?
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let line = lines[i].0 + 1;
+                if row.iter().all(|n| n == &Number::Empty) {
+                    return Err(anyhow!(
+                        "Line {} has no digits for the problem at column {} (was the line trimmed?)",
+                        line,
+                        first_column + 1
+                    ));
+                }
+                Self::numbers_to_usize(row).map_err(|e| {
+                    anyhow!("Line {}, problem at column {}: {:#}", line, first_column + 1, e)
+                })
+            })
+            .collect::<Result<Vec<usize>>>()?;
         Ok(Problem {
             rows,
             columns,
             operator,
+            expected_rows: None,
+            expected_columns: None,
         })
     }
 
@@ -44,8 +74,10 @@ impl Problem {
 
         for n in numbers {
             if let Number::Digit(d) = n {
-                number *= 10;
-                number += *d as usize;
+                number = number
+                    .checked_mul(10)
+                    .and_then(|number| number.checked_add(*d as usize))
+                    .context("Number does not fit usize")?;
             }
         }
 
@@ -80,80 +112,305 @@ impl TryFrom<char> for Number {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 enum Operator {
+    /// `+`
     Add,
+    /// `*`
     Multiply,
+    /// `-`, left-associative: a - b - c = (a - b) - c
+    Subtract,
+    /// `/`, integer division truncating towards zero, left-associative
+    Divide,
+    /// `^`, right-associative: a ^ b ^ c = a ^ (b ^ c)
+    Exponent,
+    /// `<`, smallest operand
+    Min,
+    /// `>`, largest operand
+    Max,
 }
 
 impl TryFrom<char> for Operator {
     type Error = anyhow::Error;
 
     fn try_from(value: char) -> Result<Self> {
-        if value == '+' {
-            Ok(Operator::Add)
-        } else if value == '*' {
-            Ok(Operator::Multiply)
-        } else {
-            Err(anyhow!(
-                "Invalid operator character '{}'. Expected '+' or '*'.",
+        match value {
+            '+' => Ok(Operator::Add),
+            '*' => Ok(Operator::Multiply),
+            '-' => Ok(Operator::Subtract),
+            '/' => Ok(Operator::Divide),
+            '^' => Ok(Operator::Exponent),
+            '<' => Ok(Operator::Min),
+            '>' => Ok(Operator::Max),
+            _ => Err(anyhow!(
+                "Invalid operator character '{}'. Expected one of '+', '*', '-', '/', '^', '<' or '>'.",
                 value
-            ))
+            )),
         }
     }
 }
 
+impl Operator {
+    fn symbol(&self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Multiply => '*',
+            Operator::Subtract => '-',
+            Operator::Divide => '/',
+            Operator::Exponent => '^',
+            Operator::Min => '<',
+            Operator::Max => '>',
+        }
+    }
+
+    /// Applies the operator to operands in reading order
+    fn evaluate(&self, operands: &[usize]) -> Result<i128> {
+        let overflow = || anyhow!("Overflow evaluating {:?} of {:?}", self, operands);
+
+        let mut operands = operands.iter().map(|&n| n as i128);
+        let first = operands.next().context("No operands")?;
+
+        match self {
+            Operator::Add => {
+                operands.try_fold(first, |acc, n| acc.checked_add(n).ok_or_else(overflow))
+            }
+            Operator::Multiply => {
+                operands.try_fold(first, |acc, n| acc.checked_mul(n).ok_or_else(overflow))
+            }
+            Operator::Subtract => {
+                operands.try_fold(first, |acc, n| acc.checked_sub(n).ok_or_else(overflow))
+            }
+            Operator::Divide => operands.try_fold(first, |acc, n| {
+                if n == 0 {
+                    return Err(anyhow!("Division by zero in {:?}", self));
+                }
+                Ok(acc / n)
+            }),
+            Operator::Exponent => {
+                // Right-associative: fold from the last operand
+                let operands: Vec<i128> = std::iter::once(first).chain(operands).collect();
+                operands.iter().rev().skip(1).try_fold(
+                    operands[operands.len() - 1],
+                    |exponent, &base| match base {
+                        // Exponents are never negative, any size works for these bases
+                        0 => Ok(if exponent == 0 { 1 } else { 0 }),
+                        1 => Ok(1),
+                        _ => {
+                            let exponent = u32::try_from(exponent).map_err(|_| overflow())?;
+                            base.checked_pow(exponent).ok_or_else(overflow)
+                        }
+                    },
+                )
+            }
+            Operator::Min => Ok(operands.fold(first, i128::min)),
+            Operator::Max => Ok(operands.fold(first, i128::max)),
+        }
+    }
+}
+
+/// Where the operator row of each block is placed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperatorRow {
+    /// Below the digits, blocks end with their operator (or result) row
+    Bottom,
+    /// Above the digits, blocks start with their operator (or result) row
+    Top,
+}
+
+/// Largest tab width accepted by [`parse_puzzle_with_options`], tabs expand to at most that many spaces
+pub const MAX_TAB_WIDTH: usize = 64;
+
+/// Options to read worksheets that were not saved as a perfect rectangle
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// Tabs are expanded to the next multiple of `tab_width` columns, at most [`MAX_TAB_WIDTH`]
+    pub tab_width: usize,
+    pub operator_row: OperatorRow,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            tab_width: 8,
+            operator_row: OperatorRow::Bottom,
+        }
+    }
+}
+
+/// Line of the input, with its index to report errors
+type Line = (usize, Vec<char>);
+
 pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
+    parse_puzzle_with_options(input, &ParseOptions::default())
+}
+
+/// The worksheet is made of blocks of problems separated by blank lines.
+/// A blank line only separates blocks next to an operator or result row (depending on [`OperatorRow`]),
+/// otherwise it is a line of the block which lost its digits.
+///
+/// A block can have a result row, starting with `=`, listing the expected result of each of its problems:
+/// `ROW` or `ROW/COLUMN` for the row and column readings, `?` when unknown.
+///
+/// Lines of a block shorter than the longest one (e.g. with trailing whitespace stripped) are padded with spaces.
+pub fn parse_puzzle_with_options(input: &str, options: &ParseOptions) -> Result<Puzzle> {
+    if !(1..=MAX_TAB_WIDTH).contains(&options.tab_width) {
+        return Err(anyhow!(
+            "Tab width must be between 1 and {}, got {}",
+            MAX_TAB_WIDTH,
+            options.tab_width
+        ));
+    }
+
     // Read the whole input into memory to read column by column
-    let lines: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+    let lines: Vec<Line> = input
+        .lines()
+        .map(|line| expand_tabs(line, options.tab_width))
+        .enumerate()
+        .collect();
 
     if lines.is_empty() {
         return Err(anyhow!("Input is empty"));
     }
 
-    let num_lines = lines.len();
+    let mut problems: Vec<Problem> = Vec::new();
+
+    for block in split_blocks(&lines, options.operator_row) {
+        problems.extend(parse_block(block, options.operator_row)?);
+    }
+
+    Ok(Puzzle { problems })
+}
+
+fn is_blank(line: &[char]) -> bool {
+    line.iter().all(|c| c.is_whitespace())
+}
+
+fn is_operator_row(line: &[char]) -> bool {
+    !is_blank(line)
+        && line
+            .iter()
+            .all(|c| c.is_whitespace() || Operator::try_from(*c).is_ok())
+}
+
+fn is_result_row(line: &[char]) -> bool {
+    line.iter().find(|c| !c.is_whitespace()) == Some(&'=')
+}
+
+/// Non-empty blocks of lines, without separating blank lines
+fn split_blocks(lines: &[Line], operator_row: OperatorRow) -> Vec<Vec<Line>> {
+    let closes_block = |line: Option<&Line>| match line {
+        Some((_, line)) => is_operator_row(line) || is_result_row(line),
+        None => true,
+    };
+
+    let mut blocks: Vec<Vec<Line>> = Vec::new();
+    let mut block: Vec<Line> = Vec::new();
 
-    let line_length = lines[0].len();
+    for (i, line) in lines.iter().enumerate() {
+        if is_blank(&line.1) {
+            let is_separator = match operator_row {
+                OperatorRow::Bottom => closes_block(lines[..i].iter().rfind(|l| !is_blank(&l.1))),
+                OperatorRow::Top => closes_block(lines[i + 1..].iter().find(|l| !is_blank(&l.1))),
+            };
 
-    if lines.iter().any(|line| line.len() != line_length) {
-        return Err(anyhow!("Not all lines have the same length"));
+            if is_separator {
+                if !block.is_empty() {
+                    blocks.push(std::mem::take(&mut block));
+                }
+                continue;
+            }
+        }
+
+        block.push(line.clone());
     }
 
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+/// Problems of a block of lines, from left to right
+fn parse_block(mut block: Vec<Line>, operator_row: OperatorRow) -> Result<Vec<Problem>> {
+    let mut result_rows = block.iter().filter(|(_, line)| is_result_row(line));
+    let result_row = result_rows.next().cloned();
+    if let Some((index, _)) = result_rows.next() {
+        return Err(anyhow!("Line {}: block has several result rows", index + 1));
+    }
+    block.retain(|(_, line)| !is_result_row(line));
+
+    let Some((operator_index, operator_line)) = (match operator_row {
+        OperatorRow::Bottom => block.pop(),
+        OperatorRow::Top if !block.is_empty() => Some(block.remove(0)),
+        OperatorRow::Top => None,
+    }) else {
+        let (index, _) = result_row.context("Empty block")?;
+        return Err(anyhow!("Line {}: result row without problems", index + 1));
+    };
+
+    let line_length = block
+        .iter()
+        .map(|(_, line)| line.len())
+        .chain(std::iter::once(operator_line.len()))
+        .max()
+        .unwrap_or(0);
+
+    let padded = |line: &[char]| -> Vec<char> {
+        let mut line = line.to_vec();
+        line.resize(line_length, ' ');
+        line
+    };
+    let operator_line = padded(&operator_line);
+    let digit_lines: Vec<Line> = block
+        .iter()
+        .map(|(index, line)| (*index, padded(line)))
+        .collect();
+
     let mut problems: Vec<Problem> = Vec::new();
 
-    let mut curr_operator: Option<Operator> = None;
+    let mut curr_operator: Option<(Operator, usize)> = None;
     let mut curr_columns: Vec<Vec<Number>> = Vec::new();
 
     // TODO: I think this code can be simplified. In particular, I could try using a closure to avoid code duplication for pushing a problem.
     // Maybe the code can be smaller as well by doing checks differently and in particular less sequentially (do more things at a time).
     for column_index in 0..line_length {
-        let column: Vec<char> = lines.iter().map(|line| line[column_index]).collect();
-
-        let numbers: Vec<Number> = column[0..num_lines - 1]
+        let numbers: Vec<Number> = digit_lines
             .iter()
-            .map(|c| (*c).try_into())
+            .map(|(index, line)| {
+                Number::try_from(line[column_index])
+                    .map_err(|e| anyhow!("Line {}, column {}: {}", index + 1, column_index + 1, e))
+            })
             .collect::<Result<Vec<Number>>>()?;
 
-        let operator_char = column[num_lines - 1];
+        let operator_char = operator_line[column_index];
         let operator: Option<Operator> = if operator_char == ' ' {
             None
         } else {
-            Some(operator_char.try_into()?)
+            Some(Operator::try_from(operator_char).map_err(|e| {
+                anyhow!(
+                    "Line {}, column {}: {}",
+                    operator_index + 1,
+                    column_index + 1,
+                    e
+                )
+            })?)
         };
 
         if let Some(operator) = operator {
             // New operator, we are reading a new problem
 
             // If we have a current problem defined, push it
-            if let Some(curr_operator) = curr_operator
+            if let Some((curr_operator, first_column)) = curr_operator
                 && !curr_columns.is_empty()
             {
-                let problem = Problem::new(&curr_columns, curr_operator)?;
+                let problem =
+                    Problem::new(&curr_columns, &digit_lines, curr_operator, first_column)?;
                 problems.push(problem);
             }
 
             // Update current operator and clear stored numbers
-            curr_operator = Some(operator);
+            curr_operator = Some((operator, column_index));
             curr_columns.clear();
         }
 
@@ -164,34 +421,267 @@ pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     }
 
     // Push last problem
-    if let Some(curr_operator) = curr_operator
+    if let Some((curr_operator, first_column)) = curr_operator
         && !curr_columns.is_empty()
     {
-        let problem = Problem::new(&curr_columns, curr_operator)?;
+        let problem = Problem::new(&curr_columns, &digit_lines, curr_operator, first_column)?;
         problems.push(problem);
     }
 
-    Ok(Puzzle { problems })
+    if let Some((index, line)) = result_row {
+        let line: String = line.iter().collect();
+        let tokens: Vec<&str> = line.trim_start()[1..].split_whitespace().collect();
+
+        if tokens.len() != problems.len() {
+            return Err(anyhow!(
+                "Line {}: expected {} results, one per problem, got {}",
+                index + 1,
+                problems.len(),
+                tokens.len()
+            ));
+        }
+
+        for (problem, token) in problems.iter_mut().zip(tokens) {
+            (problem.expected_rows, problem.expected_columns) = parse_expected(token)
+                .map_err(|e| anyhow!("Line {}: invalid result '{}': {}", index + 1, token, e))?;
+        }
+    }
+
+    Ok(problems)
 }
 
-pub fn solve_puzzle(puzzle: Puzzle) -> Solution {
-    let mut task_1 = 0;
-    let mut task_2 = 0;
+/// `ROW`, `ROW/COLUMN` or `?` for an unknown result
+fn parse_expected(token: &str) -> Result<(Option<i128>, Option<i128>)> {
+    let parse = |value: &str| -> Result<Option<i128>> {
+        match value {
+            "?" => Ok(None),
+            value => Ok(Some(value.parse()?)),
+        }
+    };
 
-    for problem in &puzzle.problems {
-        match problem.operator {
-            Operator::Add => {
-                task_1 += problem.rows.iter().sum::<usize>();
-                task_2 += problem.columns.iter().sum::<usize>();
+    match token.split_once('/') {
+        Some((rows, columns)) => Ok((parse(rows)?, parse(columns)?)),
+        None => Ok((parse(token)?, None)),
+    }
+}
+
+/// Replaces tabs with spaces up to the next tab stop
+fn expand_tabs(line: &str, tab_width: usize) -> Vec<char> {
+    let mut chars: Vec<char> = Vec::with_capacity(line.len());
+
+    for c in line.chars() {
+        if c == '\t' {
+            let next_stop = (chars.len() / tab_width + 1) * tab_width;
+            chars.resize(next_stop, ' ');
+        } else {
+            chars.push(c);
+        }
+    }
+
+    chars
+}
+
+/// Fails if a problem can't be evaluated (division by zero, overflow) or a grand total overflows or is negative
+pub fn solve_puzzle(puzzle: Puzzle) -> Result<Solution> {
+    let (task_1, task_2) = grand_totals(&puzzle)?;
+
+    Ok(Solution {
+        task_1: usize::try_from(task_1)
+            .with_context(|| format!("Task 1 grand total does not fit usize: {}", task_1))?,
+        task_2: usize::try_from(task_2)
+            .with_context(|| format!("Task 2 grand total does not fit usize: {}", task_2))?,
+    })
+}
+
+/// Sum of the results of all problems, reading rows (task 1) and columns (task 2)
+pub fn grand_totals(puzzle: &Puzzle) -> Result<(i128, i128)> {
+    let mut task_1: i128 = 0;
+    let mut task_2: i128 = 0;
+
+    for (i, problem) in puzzle.problems.iter().enumerate() {
+        let overflow = |task| {
+            anyhow!(
+                "Overflow adding problem {} to the task {} grand total",
+                i + 1,
+                task
+            )
+        };
+
+        task_1 = task_1
+            .checked_add(problem.operator.evaluate(&problem.rows)?)
+            .ok_or_else(|| overflow(1))?;
+        task_2 = task_2
+            .checked_add(problem.operator.evaluate(&problem.columns)?)
+            .ok_or_else(|| overflow(2))?;
+    }
+
+    Ok((task_1, task_2))
+}
+
+/// Reading of the operands of a problem
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reading {
+    Rows,
+    Columns,
+}
+
+impl Display for Reading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reading::Rows => write!(f, "rows"),
+            Reading::Columns => write!(f, "columns"),
+        }
+    }
+}
+
+/// Problem whose result differs from the one given by the worksheet
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    /// Index of the problem, from 0
+    pub problem: usize,
+    pub reading: Reading,
+    pub expected: i128,
+    pub actual: i128,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Problem {} ({}): expected {}, got {}",
+            self.problem + 1,
+            self.reading,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+/// Problems not matching the results rows of the worksheet
+pub fn mismatches(puzzle: &Puzzle) -> Result<Vec<Mismatch>> {
+    let mut res = Vec::new();
+
+    for (i, problem) in puzzle.problems.iter().enumerate() {
+        let readings = [
+            (Reading::Rows, &problem.rows, problem.expected_rows),
+            (Reading::Columns, &problem.columns, problem.expected_columns),
+        ];
+
+        for (reading, operands, expected) in readings {
+            if let Some(expected) = expected {
+                let actual = problem.operator.evaluate(operands)?;
+                if actual != expected {
+                    res.push(Mismatch {
+                        problem: i,
+                        reading,
+                        expected,
+                        actual,
+                    });
+                }
             }
-            Operator::Multiply => {
-                task_1 += problem.rows.iter().product::<usize>();
-                task_2 += problem.columns.iter().product::<usize>();
+        }
+    }
+
+    Ok(res)
+}
+
+/// How the two readings of a problem are laid out by [`render_worksheet`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorksheetLayout {
+    /// Row reading above column reading
+    Stacked,
+    /// Row reading and column reading next to each other
+    SideBySide,
+}
+
+/// Each problem as a vertical worksheet, once with its row operands and once with its column operands.
+/// Problems that can't be evaluated show the error instead of a result.
+pub fn render_worksheet(puzzle: &Puzzle, layout: WorksheetLayout) -> String {
+    let mut res = String::new();
+
+    for (i, problem) in puzzle.problems.iter().enumerate() {
+        let rows = worksheet_block(
+            "rows",
+            &problem.rows,
+            &problem.operator,
+            problem.expected_rows,
+        );
+        let columns = worksheet_block(
+            "columns",
+            &problem.columns,
+            &problem.operator,
+            problem.expected_columns,
+        );
+
+        res.push_str(&format!("Problem {}\n", i + 1));
+
+        match layout {
+            WorksheetLayout::Stacked => {
+                for line in rows.iter().chain(columns.iter()) {
+                    res.push_str(&format!("{}\n", line));
+                }
             }
+            WorksheetLayout::SideBySide => {
+                let width = rows.iter().map(String::len).max().unwrap_or(0);
+                let blank = String::new();
+                for k in 0..rows.len().max(columns.len()) {
+                    let left = rows.get(k).unwrap_or(&blank);
+                    let right = columns.get(k).unwrap_or(&blank);
+                    res.push_str(format!("{:<width$}   {}", left, right).trim_end());
+                    res.push('\n');
+                }
+            }
+        }
+
+        res.push('\n');
+    }
+
+    res
+}
+
+/// Title, operands right-aligned with the operator on the last one, separator and result.
+/// The expected result is shown below when it differs.
+fn worksheet_block(
+    title: &str,
+    operands: &[usize],
+    operator: &Operator,
+    expected: Option<i128>,
+) -> Vec<String> {
+    let evaluated = operator.evaluate(operands);
+    let result = match &evaluated {
+        Ok(result) => result.to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+
+    let operand_width = operands
+        .iter()
+        .map(|n| n.to_string().len())
+        .max()
+        .unwrap_or(0);
+    // Operator, space, operands
+    let width = (operand_width + 2).max(result.len()).max(title.len());
+
+    let mut lines = vec![title.to_string()];
+
+    for (k, operand) in operands.iter().enumerate() {
+        let prefix = if k + 1 == operands.len() {
+            operator.symbol()
+        } else {
+            ' '
         };
+        lines.push(format!("{}{:>w$}", prefix, operand, w = width - 1));
     }
 
-    Solution { task_1, task_2 }
+    lines.push("-".repeat(width));
+    lines.push(format!("{:>width$}", result));
+
+    if let Some(expected) = expected
+        && evaluated.ok() != Some(expected)
+    {
+        lines.push(format!("expected {}", expected));
+    }
+
+    lines
 }
 
 /// Transpose matrix NxM to matrix MxN
@@ -224,23 +714,407 @@ fn transpose<T: Clone>(matrix: &[Vec<T>]) -> Result<Vec<Vec<T>>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_example_puzzle() {
         let input = include_str!("../inputs/examples/day_6");
         let puzzle = parse_puzzle(input).unwrap();
         dbg!(&puzzle);
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 4277556);
         assert_eq!(solution.task_2, 3263827);
     }
 
+    #[test]
+    fn test_trimmed_lines() {
+        let input = include_str!("../inputs/examples/day_6");
+        let trimmed: Vec<&str> = input.lines().map(|line| line.trim_end()).collect();
+        let puzzle = parse_puzzle(&trimmed.join("\n")).unwrap();
+        let solution = solve_puzzle(puzzle).unwrap();
+        assert_eq!(solution.task_1, 4277556);
+        assert_eq!(solution.task_2, 3263827);
+    }
+
+    #[test]
+    fn test_tabs() {
+        let input = "12\t3\n4\t56\n+\t*";
+        let options = ParseOptions {
+            tab_width: 4,
+            ..ParseOptions::default()
+        };
+        let puzzle = parse_puzzle_with_options(input, &options).unwrap();
+        assert_eq!(puzzle.problems[0].rows, vec![12, 4]);
+        assert_eq!(puzzle.problems[1].rows, vec![3, 56]);
+
+        assert_eq!(expand_tabs("a\tb", 4), vec!['a', ' ', ' ', ' ', 'b']);
+        assert_eq!(expand_tabs("abcd\tb", 4).len(), 9);
+        assert!(
+            parse_puzzle_with_options(
+                input,
+                &ParseOptions {
+                    tab_width: 0,
+                    ..ParseOptions::default()
+                }
+            )
+            .is_err()
+        );
+        let error = parse_puzzle_with_options(
+            input,
+            &ParseOptions {
+                tab_width: usize::MAX,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Tab width must be between 1 and 64, got {}", usize::MAX)
+        );
+    }
+
+    #[test]
+    fn test_unrecoverable_line() {
+        // Second line lost its number
+        let input = "12 3\n\n+  *";
+        let error = parse_puzzle(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 2 has no digits for the problem at column 1 (was the line trimmed?)"
+        );
+
+        let input = "12 3\n4x 5\n+  *";
+        let error = parse_puzzle(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 2, column 2: Invalid char (= 'x') for number. Expected digit or whitespace"
+        );
+
+        let input = "12 3\n45 6\n+  %";
+        let error = parse_puzzle(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 3, column 4: Invalid operator character '%'. Expected one of '+', '*', '-', '/', '^', '<' or '>'."
+        );
+    }
+
+    #[test]
+    fn test_grand_total_overflow() {
+        // Each problem fits i128 (1.3e38), their sum does not
+        let input = "13000000000000000000 13000000000000000000\n10000000000000000000 10000000000000000000\n*                    *";
+        let puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(
+            grand_totals(&puzzle).unwrap_err().to_string(),
+            "Overflow adding problem 2 to the task 1 grand total"
+        );
+    }
+
+    #[test]
+    fn test_number_too_large() {
+        let input = "123456789012345678901\n4\n+";
+        let error = parse_puzzle(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 1, problem at column 1: Number does not fit usize"
+        );
+
+        let input = format!("{}+", "9\n".repeat(21));
+        let error = parse_puzzle(&input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Problem at column 1: Number does not fit usize"
+        );
+    }
+
+    #[test]
+    fn test_render_worksheet() {
+        let input = "123 328\n 45 64 \n  6 98 \n*   +  ";
+        let puzzle = parse_puzzle(input).unwrap();
+
+        let stacked = render_worksheet(&puzzle, WorksheetLayout::Stacked);
+        let expected_first = "\
+Problem 1
+rows
+  123
+   45
+*   6
+-----
+33210
+columns
+    356
+     24
+*     1
+-------
+   8544
+";
+        assert!(stacked.starts_with(expected_first));
+        assert!(stacked.contains("Problem 2\n"));
+
+        let side_by_side = render_worksheet(&puzzle, WorksheetLayout::SideBySide);
+        let expected_first = "\
+Problem 1
+rows    columns
+  123       356
+   45        24
+*   6   *     1
+-----   -------
+33210      8544
+";
+        assert!(side_by_side.starts_with(expected_first));
+    }
+
+    #[test]
+    fn test_render_worksheet_error() {
+        let puzzle = parse_puzzle("4\n0\n/").unwrap();
+        let worksheet = render_worksheet(&puzzle, WorksheetLayout::Stacked);
+        assert!(worksheet.contains("error: Division by zero"));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = "\
+123 328
+ 45 64
+  6 98
+*   +
+
+ 51 64
+387 23
+215 314
+*   +
+";
+        let puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(puzzle.problems.len(), 4);
+        assert_eq!(puzzle.problems[2].rows, vec![51, 387, 215]);
+        assert_eq!(puzzle.problems[3].columns, vec![4, 431, 623]);
+
+        // Operators on top, blank lines before the first block ignored
+        let input = "
+*   +
+123 328
+ 45 64
+  6 98
+
+*   +
+ 51 64
+387 23
+215 314
+";
+        let options = ParseOptions {
+            operator_row: OperatorRow::Top,
+            ..ParseOptions::default()
+        };
+        let top = parse_puzzle_with_options(input, &options).unwrap();
+        assert_eq!(grand_totals(&top).unwrap(), grand_totals(&puzzle).unwrap());
+
+        // A blank line inside a block is a line without digits
+        let input = "*   +\n123 328\n\n  6 98\n";
+        let error = parse_puzzle_with_options(input, &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 3 has no digits for the problem at column 1 (was the line trimmed?)"
+        );
+    }
+
+    #[test]
+    fn test_result_rows() {
+        let input = "\
+123 328
+ 45 64
+  6 98
+*   +
+= 33210/8544 ?/625
+
+4 6
+2 3
+- /
+= 2 3
+";
+        let puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(
+            mismatches(&puzzle).unwrap(),
+            vec![Mismatch {
+                problem: 3,
+                reading: Reading::Rows,
+                expected: 3,
+                actual: 2,
+            }]
+        );
+        assert_eq!(
+            mismatches(&puzzle).unwrap()[0].to_string(),
+            "Problem 4 (rows): expected 3, got 2"
+        );
+        assert!(render_worksheet(&puzzle, WorksheetLayout::Stacked).contains("\nexpected 3\n"));
+
+        let error = parse_puzzle("1 2\n+ *\n= 1\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 3: expected 2 results, one per problem, got 1"
+        );
+        assert_eq!(
+            parse_puzzle("1\n+\n= x\n").unwrap_err().to_string(),
+            "Line 3: invalid result 'x': invalid digit found in string"
+        );
+        assert!(parse_puzzle("1\n+\n= 1\n= 1\n").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_operators() {
+        assert_eq!(Operator::Add.evaluate(&[1, 2, 3]).unwrap(), 6);
+        assert_eq!(Operator::Multiply.evaluate(&[2, 3, 4]).unwrap(), 24);
+        assert_eq!(Operator::Subtract.evaluate(&[1, 2, 3]).unwrap(), -4);
+        assert_eq!(Operator::Divide.evaluate(&[100, 7, 2]).unwrap(), 7);
+        assert_eq!(Operator::Exponent.evaluate(&[2, 3, 2]).unwrap(), 512);
+        assert_eq!(Operator::Min.evaluate(&[5, 2, 9]).unwrap(), 2);
+        assert_eq!(Operator::Max.evaluate(&[5, 2, 9]).unwrap(), 9);
+
+        assert!(Operator::Divide.evaluate(&[1, 0]).is_err());
+        assert!(Operator::Exponent.evaluate(&[10, 100]).is_err());
+
+        // Exponents too large for `u32`
+        assert_eq!(Operator::Exponent.evaluate(&[1, 5000000000]).unwrap(), 1);
+        assert_eq!(Operator::Exponent.evaluate(&[0, 5000000000]).unwrap(), 0);
+        assert_eq!(Operator::Exponent.evaluate(&[0, 0]).unwrap(), 1);
+        assert_eq!(Operator::Exponent.evaluate(&[2, 1, 5000000000]).unwrap(), 2);
+        assert!(Operator::Exponent.evaluate(&[2, 5000000000]).is_err());
+    }
+
+    #[test]
+    fn test_reading_direction() {
+        // Rows: 64 - 23 = 41, columns right to left: 43 - 62 = -19
+        let input = "
+64
+23
+- "
+        .trim_start();
+        let puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(puzzle.problems[0].rows, vec![64, 23]);
+        assert_eq!(puzzle.problems[0].columns, vec![43, 62]);
+        assert_eq!(grand_totals(&puzzle).unwrap(), (41, -19));
+
+        let result = solve_puzzle(puzzle);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Task 2 grand total does not fit usize: -19")
+        );
+    }
+
+    #[test]
+    fn test_non_commutative_operators() {
+        let input = "
+ 8 2 100
+ 3 3  25
+ 2 2   4
+ ^ / -  "
+            .trim_start_matches('\n');
+        let puzzle = parse_puzzle(input).unwrap();
+        let solution = solve_puzzle(puzzle).unwrap();
+        // Rows: 8^(3^2) + 2/3/2 (= 0) + (100-25-4)
+        assert_eq!(solution.task_1, 134217728 + 71);
+        // Columns right to left: 832 + 232 + (54-2-1)
+        assert_eq!(solution.task_2, 832 + 232 + 51);
+    }
+
     #[test]
     fn test_real_input() {
         let input = include_str!("../inputs/day_6");
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 4693419406682);
         assert_eq!(solution.task_2, 9029931401920);
     }
+
+    /// Problem laid out in a `width` columns wide box: an operator and one number per row,
+    /// each number given by its digits and its offset in the box
+    #[derive(Debug, Clone)]
+    struct Layout {
+        operator: char,
+        width: usize,
+        rows: Vec<(Vec<u32>, usize)>,
+    }
+
+    impl Layout {
+        /// Row `i` of the box, padded with spaces
+        fn line(&self, i: usize) -> String {
+            let (digits, offset) = &self.rows[i];
+            let mut line = vec![' '; self.width];
+            for (j, digit) in digits.iter().enumerate() {
+                line[offset + j] = char::from_digit(*digit, 10).unwrap();
+            }
+            line.into_iter().collect()
+        }
+
+        /// Reference implementation: reads the box cell by cell, skipping empty columns
+        fn evaluate(&self) -> (i128, i128) {
+            let lines: Vec<Vec<char>> = (0..self.rows.len())
+                .map(|i| self.line(i).chars().collect())
+                .collect();
+            let number = |digits: &[char]| -> Option<i128> {
+                let digits: String = digits.iter().filter(|c| c.is_ascii_digit()).collect();
+                digits.parse().ok()
+            };
+
+            let rows: Vec<i128> = lines.iter().filter_map(|line| number(line)).collect();
+            let columns: Vec<i128> = (0..self.width)
+                .rev()
+                .filter_map(|j| number(&lines.iter().map(|line| line[j]).collect::<Vec<char>>()))
+                .collect();
+
+            let apply = |operands: &[i128]| -> i128 {
+                let rest = operands[1..].iter();
+                match self.operator {
+                    '+' => operands.iter().sum(),
+                    '*' => operands.iter().product(),
+                    '-' => operands[0] - rest.sum::<i128>(),
+                    '<' => *operands.iter().min().unwrap(),
+                    '>' => *operands.iter().max().unwrap(),
+                    other => unreachable!("No generated operator {}", other),
+                }
+            };
+
+            (apply(&rows), apply(&columns))
+        }
+    }
+
+    fn layout(height: usize) -> impl Strategy<Value = Layout> {
+        let operator = prop::sample::select(vec!['+', '*', '-', '<', '>']);
+        (operator, 1..=4usize).prop_flat_map(move |(operator, width)| {
+            let row = (1..=width).prop_flat_map(move |length| {
+                (prop::collection::vec(0..10u32, length), 0..=width - length)
+            });
+            prop::collection::vec(row, height).prop_map(move |rows| Layout {
+                operator,
+                width,
+                rows,
+            })
+        })
+    }
+
+    fn worksheet() -> impl Strategy<Value = Vec<Layout>> {
+        (1..5usize).prop_flat_map(|height| prop::collection::vec(layout(height), 1..5))
+    }
+
+    proptest! {
+        #[test]
+        fn test_grand_totals_match_naive(layouts in worksheet()) {
+            let height = layouts[0].rows.len();
+            let mut lines: Vec<String> = (0..height)
+                .map(|i| layouts.iter().map(|layout| layout.line(i)).collect::<Vec<String>>().join(" "))
+                .collect();
+            lines.push(
+                layouts
+                    .iter()
+                    .map(|layout| format!("{:<width$}", layout.operator, width = layout.width))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            );
+
+            let puzzle = parse_puzzle(&lines.join("\n")).unwrap();
+            let expected = layouts.iter().map(Layout::evaluate).fold((0, 0), |(rows, columns), (r, c)| (rows + r, columns + c));
+            prop_assert_eq!(grand_totals(&puzzle).unwrap(), expected);
+        }
+    }
 }
@@ -1,7 +1,13 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use num_bigint::{BigRng09, BigUint};
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::Display;
 
-use crate::common::Solution;
+use crate::{
+    common::Solution,
+    grid_image::{Grid, HeatmapScale, heat_colour},
+};
 
 pub struct Puzzle {
     manifold: Vec<Vec<Cell>>,
@@ -19,36 +25,194 @@ impl Display for Puzzle {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum Cell {
     Empty,
-    Beam,
+    /// Emits a beam downward
+    Source,
+    /// Splits a beam into two parallel beams, on both sides of the splitter
     Splitter,
+    /// `/`
+    MirrorSlash,
+    /// `\`
+    MirrorBackslash,
+    /// Absorbs beams
+    Wall,
 }
 
-impl Display for Cell {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Cell {
+    fn to_char(self) -> char {
         match self {
-            Cell::Empty => write!(f, "."),
-            Cell::Beam => write!(f, "|"),
-            Cell::Splitter => write!(f, "^"),
+            Cell::Empty => '.',
+            Cell::Source => 'S',
+            Cell::Splitter => '^',
+            Cell::MirrorSlash => '/',
+            Cell::MirrorBackslash => '\\',
+            Cell::Wall => '#',
         }
     }
 }
 
+impl Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
 impl TryFrom<char> for Cell {
     type Error = anyhow::Error;
 
     fn try_from(value: char) -> Result<Self> {
         match value {
             '.' => Ok(Cell::Empty),
-            'S' => Ok(Cell::Beam), // Start of beam
+            'S' => Ok(Cell::Source), // Start of beam
             '^' => Ok(Cell::Splitter),
-            _ => Err(anyhow::anyhow!("Invalid cell character: '{}'", value)),
+            '/' => Ok(Cell::MirrorSlash),
+            '\\' => Ok(Cell::MirrorBackslash),
+            '#' => Ok(Cell::Wall),
+            _ => Err(anyhow!("Invalid cell character: '{}'", value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    /// Directions on both sides, where a splitter sends the beam
+    fn perpendicular(self) -> [Direction; 2] {
+        match self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+        }
+    }
+
+    fn reflect(self, mirror: Cell) -> Direction {
+        match (mirror, self) {
+            (Cell::MirrorSlash, Direction::Down) => Direction::Left,
+            (Cell::MirrorSlash, Direction::Up) => Direction::Right,
+            (Cell::MirrorSlash, Direction::Left) => Direction::Down,
+            (Cell::MirrorSlash, Direction::Right) => Direction::Up,
+            (Cell::MirrorBackslash, Direction::Down) => Direction::Right,
+            (Cell::MirrorBackslash, Direction::Up) => Direction::Left,
+            (Cell::MirrorBackslash, Direction::Left) => Direction::Up,
+            (Cell::MirrorBackslash, Direction::Right) => Direction::Down,
+            (_, direction) => direction,
         }
     }
 }
 
+type Position = (usize, usize);
+
+/// Beam in a cell, travelling in a direction
+type State = (Position, Direction);
+
+/// Side taken by a timeline at a splitter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Choice {
+    pub splitter: (usize, usize),
+    pub side: Direction,
+}
+
+/// What happens to a split beam that would land outside of the manifold
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EdgePolicy {
+    /// The beam is lost, it does not make a timeline
+    #[default]
+    Lost,
+    /// The beam bounces off the edge and continues on the other side of the splitter
+    Reflected,
+    /// Fail with [`BeamError::SplitOutOfBounds`]
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BeamError {
+    /// The splitter at `splitter` sends a beam on `side`, outside of the manifold
+    SplitOutOfBounds {
+        splitter: (usize, usize),
+        side: Direction,
+    },
+    /// The splitter at `splitter` sends a beam directly into the splitter at `neighbour`
+    AdjacentSplitters {
+        splitter: (usize, usize),
+        neighbour: (usize, usize),
+    },
+}
+
+impl Display for BeamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BeamError::SplitOutOfBounds { splitter, side } => write!(
+                f,
+                "Splitter at {:?} sends a beam {:?} outside of the manifold",
+                splitter, side
+            ),
+            BeamError::AdjacentSplitters {
+                splitter,
+                neighbour,
+            } => write!(
+                f,
+                "Splitter at {:?} sends a beam into the splitter at {:?}",
+                splitter, neighbour
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BeamError {}
+
+/// Path of a beam from a source until it leaves the manifold
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Timeline {
+    pub source: (usize, usize),
+    pub choices: Vec<Choice>,
+    /// Last cell before leaving the manifold
+    pub exit: (usize, usize),
+}
+
+impl Display for Timeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.source)?;
+        for choice in &self.choices {
+            write!(f, " {:?} at {:?},", choice.side, choice.splitter)?;
+        }
+        write!(f, " leaves at {:?}", self.exit)
+    }
+}
+
+/// Number of timelines going through a splitter, in total and by side
+#[derive(Debug, PartialEq)]
+pub struct SplitterStats {
+    pub splitter: (usize, usize),
+    pub timelines: BigUint,
+    pub sides: Vec<(Direction, BigUint)>,
+}
+
+impl Display for SplitterStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {} timelines", self.splitter, self.timelines)?;
+        for (side, count) in &self.sides {
+            write!(f, ", {} {:?}", count, side)?;
+        }
+        Ok(())
+    }
+}
+
 pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     let manifold = input
         .lines()
@@ -62,139 +226,711 @@ pub fn parse_puzzle(input: &str) -> Result<Puzzle> {
     Ok(Puzzle { manifold })
 }
 
-pub fn solve_puzzle(mut puzzle: Puzzle) -> Solution {
-    let mut task_1 = 0;
-    let mut task_2_counts: Vec<Vec<usize>> = Vec::new();
-
-    // If cell is in bounds and empty, set to beam
-    let set_beam = |i: usize,
-                    j: usize,
-                    manifold: &mut Vec<Vec<Cell>>,
-                    task_2: &mut Vec<Vec<usize>>,
-                    task_2_parent_count: usize| {
-        if let Some(cell) = manifold.get_mut(i).and_then(|row| row.get_mut(j)) {
-            match cell {
-                Cell::Empty => {
-                    *cell = Cell::Beam;
-                    task_2[i][j] += task_2_parent_count;
+/// Fails if beams loop with infinitely many timelines leaving the manifold, or a splitter is next to another one
+pub fn solve_puzzle(puzzle: Puzzle) -> Result<Solution> {
+    solve_puzzle_with_policy(puzzle, EdgePolicy::default())
+}
+
+/// Same as [`solve_puzzle`], with split beams landing outside of the manifold handled according to `policy`
+pub fn solve_puzzle_with_policy(puzzle: Puzzle, policy: EdgePolicy) -> Result<Solution> {
+    let beams = Beams::trace_with_policy(&puzzle, policy)?;
+
+    Ok(Solution {
+        task_1: beams.splits(),
+        task_2: beams.timelines()?,
+    })
+}
+
+/// Final manifold, with the number of timelines reaching each beam cell as value (to export as image).
+/// `beams` are traced from `puzzle`.
+pub fn timeline_grid(puzzle: &Puzzle, beams: &Beams) -> Grid {
+    let counts = beams.cell_counts(puzzle);
+
+    let cells = puzzle
+        .manifold
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, cell)| match cell {
+                    Cell::Empty | Cell::Source if beams.lit.contains(&(i, j)) => '|',
+                    cell => cell.to_char(),
+                })
+                .collect()
+        })
+        .collect();
+
+    // Counts too large for the image are saturated
+    let values = counts
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|count| count.map(|count| usize::try_from(count).unwrap_or(usize::MAX)))
+                .collect()
+        })
+        .collect();
+
+    Grid { cells, values }
+}
+
+/// How [`render_counts`] shows the number of timelines crossing each cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CountsDisplay {
+    /// Counts padded to the same width, so the grid stays aligned
+    FixedWidth,
+    /// Cells coloured from dark blue (fewest timelines) to yellow (most timelines)
+    Colour(HeatmapScale),
+}
+
+/// Manifold with the number of timelines crossing each cell, for the terminal. `beams` are traced from `puzzle`.
+pub fn render_counts(puzzle: &Puzzle, beams: &Beams, display: CountsDisplay) -> String {
+    let counts = beams.cell_counts(puzzle);
+    let mut output = String::new();
+
+    match display {
+        CountsDisplay::FixedWidth => {
+            let width = counts
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|count| count.to_string().len())
+                .max()
+                .unwrap_or(1);
+
+            for (row, counts) in puzzle.manifold.iter().zip(&counts) {
+                let line: Vec<String> = row
+                    .iter()
+                    .zip(counts)
+                    .map(|(cell, count)| match count {
+                        Some(count) => format!("{:>width$}", count),
+                        None => format!("{:>width$}", cell.to_char()),
+                    })
+                    .collect();
+                output.push_str(&line.join(" "));
+                output.push('\n');
+            }
+        }
+        CountsDisplay::Colour(scale) => {
+            // Counts too large for colours are saturated
+            let values: Vec<Vec<Option<usize>>> = counts
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|count| {
+                            count
+                                .as_ref()
+                                .map(|count| usize::try_from(count).unwrap_or(usize::MAX))
+                        })
+                        .collect()
+                })
+                .collect();
+            let min = values
+                .iter()
+                .flatten()
+                .flatten()
+                .min()
+                .copied()
+                .unwrap_or(0);
+            let max = values
+                .iter()
+                .flatten()
+                .flatten()
+                .max()
+                .copied()
+                .unwrap_or(0);
+
+            for (row, values) in puzzle.manifold.iter().zip(&values) {
+                for (cell, value) in row.iter().zip(values) {
+                    match (cell, value) {
+                        (cell, Some(value)) => {
+                            let (r, g, b) = heat_colour(*value, min, max, scale);
+                            let c = match cell {
+                                Cell::Empty | Cell::Source => '|',
+                                cell => cell.to_char(),
+                            };
+                            output.push_str(&format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, c));
+                        }
+                        (cell, None) => output.push(cell.to_char()),
+                    }
+                }
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+/// Number of timelines crossing each cell as CSV, one line per row of the manifold.
+/// Cells without beams are empty. `beams` are traced from `puzzle`.
+pub fn counts_csv(puzzle: &Puzzle, beams: &Beams) -> String {
+    let counts = beams.cell_counts(puzzle);
+
+    counts
+        .iter()
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .map(|count| count.as_ref().map(BigUint::to_string).unwrap_or_default())
+                .collect();
+            line.join(",") + "\n"
+        })
+        .collect()
+}
+
+/// How timelines are counted: exactly, or modulo a number when only the remainder is needed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CountMode {
+    Exact,
+    Modulo(u64),
+}
+
+/// All beams going through the manifold, from every source.
+/// Beams are states (cell, direction): beams reaching the same state merge and add their timelines.
+pub struct Beams {
+    sources: Vec<State>,
+    /// Splitters hit, with the direction of the incoming beam
+    split_events: HashSet<State>,
+    /// Cells crossed by a beam
+    lit: HashSet<Position>,
+    /// States reached one step after each state, with the side taken if the beam was split
+    next_states: HashMap<State, Vec<(State, Option<Choice>)>>,
+    /// States not in or after a cycle, in topological order
+    order: Vec<State>,
+    /// States with infinitely many timelines reaching them
+    cyclic: HashSet<State>,
+    /// States from which the beam leaves the manifold
+    exits: Vec<State>,
+}
+
+impl Beams {
+    /// Traces beams, split beams landing outside of the manifold are lost
+    pub fn trace(puzzle: &Puzzle) -> Result<Beams, BeamError> {
+        Self::trace_with_policy(puzzle, EdgePolicy::default())
+    }
+
+    pub fn trace_with_policy(puzzle: &Puzzle, policy: EdgePolicy) -> Result<Beams, BeamError> {
+        let sources: Vec<State> = puzzle
+            .manifold
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, cell)| **cell == Cell::Source)
+                    .map(move |(j, _)| ((i, j), Direction::Down))
+            })
+            .collect();
+
+        let mut split_events: HashSet<State> = HashSet::new();
+        let mut exits: Vec<State> = Vec::new();
+        let mut next_states: HashMap<State, Vec<(State, Option<Choice>)>> = HashMap::new();
+
+        // Explore all states reachable from sources
+        let mut queue: VecDeque<State> = sources.iter().copied().collect();
+        for source in &sources {
+            next_states.insert(*source, Vec::new());
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let next = puzzle.next_states(state, policy, &mut split_events, &mut exits)?;
+
+            for (next_state, _) in &next {
+                if !next_states.contains_key(next_state) {
+                    next_states.insert(*next_state, Vec::new());
+                    queue.push_back(*next_state);
                 }
-                Cell::Beam => {
-                    // Already a beam, just increment count
-                    task_2[i][j] += task_2_parent_count;
+            }
+
+            next_states.insert(state, next);
+        }
+
+        // Topological order (Kahn), states left unvisited are in or after a cycle
+        let mut in_degrees: HashMap<State, usize> =
+            next_states.keys().map(|state| (*state, 0)).collect();
+        for (next, _) in next_states.values().flatten() {
+            *in_degrees.entry(*next).or_default() += 1;
+        }
+
+        let mut queue: VecDeque<State> = in_degrees
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(state, _)| *state)
+            .collect();
+        let mut order: Vec<State> = Vec::with_capacity(next_states.len());
+
+        while let Some(state) = queue.pop_front() {
+            order.push(state);
+
+            for (next, _) in &next_states[&state] {
+                let degree = in_degrees.get_mut(next).expect("All states have a degree");
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(*next);
                 }
-                Cell::Splitter => (), // This case is quite unexpected, ignore it and don't propagate beam
             }
         }
-    };
 
-    // Initialise task 2 counts structure
-    for i in 0..puzzle.manifold.len() {
-        task_2_counts.push(vec![0; puzzle.manifold[i].len()]);
+        let cyclic: HashSet<State> = in_degrees
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .map(|(state, _)| state)
+            .collect();
+
+        let lit = next_states.keys().map(|(position, _)| *position).collect();
+
+        Ok(Beams {
+            sources,
+            split_events,
+            lit,
+            next_states,
+            order,
+            cyclic,
+            exits,
+        })
+    }
+
+    /// Number of times a beam is split
+    pub fn splits(&self) -> usize {
+        self.split_events.len()
+    }
+
+    /// Whether some beams loop forever
+    pub fn has_cycle(&self) -> bool {
+        !self.cyclic.is_empty()
+    }
+
+    /// Number of timelines in which the beam leaves the manifold.
+    /// Fails if it does not fit `usize`, see [`Beams::timeline_count`] for larger counts.
+    pub fn timelines(&self) -> Result<usize> {
+        let count = self.timeline_count(CountMode::Exact)?;
+        usize::try_from(&count)
+            .map_err(|_| anyhow!("Number of timelines does not fit usize: {}", count))
+    }
+
+    /// Number of timelines in which the beam leaves the manifold, exactly or modulo a number.
+    /// Fails if a cycle feeds beams leaving the manifold, as there are infinitely many timelines.
+    pub fn timeline_count(&self, mode: CountMode) -> Result<BigUint> {
+        if mode == CountMode::Modulo(0) {
+            return Err(anyhow!("Cannot count timelines modulo 0"));
+        }
+
+        self.check_finite()?;
+        let counts = self.state_counts(mode);
+
+        let total: BigUint = self
+            .exits
+            .iter()
+            .filter_map(|state| counts.get(state))
+            .sum();
+
+        Ok(match mode {
+            CountMode::Exact => total,
+            CountMode::Modulo(modulus) => total % modulus,
+        })
+    }
+
+    /// Fails if a cycle feeds beams leaving the manifold, as there are infinitely many timelines
+    fn check_finite(&self) -> Result<()> {
+        match self.exits.iter().find(|state| self.cyclic.contains(state)) {
+            Some(state) => Err(anyhow!(
+                "Infinitely many timelines: beams loop before leaving the manifold at {:?}",
+                state.0
+            )),
+            None => Ok(()),
+        }
+    }
 
-        if i == 0 {
-            // Initialise first counters at first row
-            for (j, cell) in puzzle.manifold[i].iter().enumerate() {
-                if cell == &Cell::Beam {
-                    task_2_counts[0][j] = 1;
+    /// Number of timelines reaching each state, missing for states in or after a cycle.
+    /// In modulo mode, counts are reduced at each step so they stay small.
+    fn state_counts(&self, mode: CountMode) -> HashMap<State, BigUint> {
+        match mode {
+            CountMode::Exact => self.counts_by(BigUint::from(1u32), |a, b| a + b),
+            CountMode::Modulo(modulus) => self
+                .counts_by(1 % modulus, |a, b| {
+                    ((*a as u128 + *b as u128) % modulus as u128) as u64
+                })
+                .into_iter()
+                .map(|(state, count)| (state, BigUint::from(count)))
+                .collect(),
+        }
+    }
+
+    /// Adds counts along beams in topological order, starting with `one` for each source
+    fn counts_by<T: Clone>(&self, one: T, add: impl Fn(&T, &T) -> T) -> HashMap<State, T> {
+        let mut counts: HashMap<State, T> = HashMap::new();
+
+        for source in &self.sources {
+            let count = match counts.get(source) {
+                Some(count) => add(count, &one),
+                None => one.clone(),
+            };
+            counts.insert(*source, count);
+        }
+
+        for state in &self.order {
+            let Some(count) = counts.get(state).cloned() else {
+                continue;
+            };
+
+            for (next, _) in &self.next_states[state] {
+                let next_count = match counts.get(next) {
+                    Some(next_count) => add(next_count, &count),
+                    None => count.clone(),
+                };
+                counts.insert(*next, next_count);
+            }
+        }
+
+        counts.retain(|state, _| !self.cyclic.contains(state));
+        counts
+    }
+
+    /// Number of timelines leaving the manifold from each state.
+    /// Fails if there are infinitely many timelines.
+    fn exit_counts(&self) -> Result<HashMap<State, BigUint>> {
+        self.check_finite()?;
+
+        let exits: HashSet<&State> = self.exits.iter().collect();
+        let mut counts: HashMap<State, BigUint> = HashMap::new();
+
+        for state in self.order.iter().rev() {
+            let mut count = BigUint::from(exits.contains(state) as u32);
+            for (next, _) in &self.next_states[state] {
+                if let Some(next_count) = counts.get(next) {
+                    count += next_count;
                 }
             }
+            counts.insert(*state, count);
         }
+
+        Ok(counts)
     }
 
-    // Loop for beam propagation
-    for i in 0..puzzle.manifold.len() {
-        for j in 0..puzzle.manifold[i].len() {
-            let cell = &puzzle.manifold[i][j];
-            match cell {
-                Cell::Empty => (),
-                Cell::Splitter => (),
-                Cell::Beam => {
-                    // If beam, propagate downwards
-                    // Need to check what is below: empty or splitter
-                    if let Some(cell_below) = puzzle
-                        .manifold
-                        .get_mut(i + 1)
-                        .and_then(|row| row.get_mut(j))
-                    {
-                        match cell_below {
-                            Cell::Empty => {
-                                // Empty below, continue beam downwards
-                                *cell_below = Cell::Beam;
-                                task_2_counts[i + 1][j] += task_2_counts[i][j];
-                            }
-                            Cell::Splitter => {
-                                // Splitter below, split beam
-                                task_1 += 1;
-                                let task_2_count = task_2_counts[i][j];
-                                set_beam(
-                                    i + 1,
-                                    j - 1,
-                                    &mut puzzle.manifold,
-                                    &mut task_2_counts,
-                                    task_2_count,
-                                );
-                                set_beam(
-                                    i + 1,
-                                    j + 1,
-                                    &mut puzzle.manifold,
-                                    &mut task_2_counts,
-                                    task_2_count,
-                                );
-                            }
-                            Cell::Beam => {
-                                // Already a beam below, just propagate count
-                                task_2_counts[i + 1][j] += task_2_counts[i][j];
-                            }
-                        }
+    /// Lazily enumerates all timelines, source by source, trying sides in the order of [`Direction::perpendicular`].
+    /// Branches without timelines are skipped, so each timeline takes a time linear in its length.
+    pub fn enumerate(&self) -> Result<Timelines<'_>> {
+        Ok(Timelines {
+            beams: self,
+            exit_counts: self.exit_counts()?,
+            exits: self.exits.iter().copied().collect(),
+            next_source: 0,
+            stack: Vec::new(),
+        })
+    }
+
+    /// Uniformly random timeline, `None` if there are none
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Option<Timeline>> {
+        let exit_counts = self.exit_counts()?;
+        let exits: HashSet<&State> = self.exits.iter().collect();
+        let count = |state: &State| exit_counts.get(state).cloned().unwrap_or_default();
+
+        let total: BigUint = self.sources.iter().map(count).sum();
+        if total == BigUint::ZERO {
+            return Ok(None);
+        }
+
+        // Index of the timeline among all of them, going down the branch containing it
+        let mut index = rng.random_biguint_below(&total);
+
+        let mut state = *self
+            .sources
+            .iter()
+            .find(|source| {
+                let source_count = count(source);
+                if index < source_count {
+                    return true;
+                }
+                index -= source_count;
+                false
+            })
+            .expect("Index is below the total");
+
+        let mut timeline = Timeline {
+            source: state.0,
+            choices: Vec::new(),
+            exit: state.0,
+        };
+
+        loop {
+            if exits.contains(&state) {
+                if index == BigUint::ZERO {
+                    timeline.exit = state.0;
+                    return Ok(Some(timeline));
+                }
+                index -= 1u32;
+            }
+
+            let (next, choice) = self.next_states[&state]
+                .iter()
+                .find(|(next, _)| {
+                    let next_count = count(next);
+                    if index < next_count {
+                        return true;
                     }
+                    index -= next_count;
+                    false
+                })
+                .expect("Index is below the number of timelines from the state");
+
+            state = *next;
+            timeline.choices.extend(choice);
+        }
+    }
+
+    /// Timelines going through each splitter hit by a beam, by position
+    pub fn splitter_stats(&self) -> Result<Vec<SplitterStats>> {
+        let exit_counts = self.exit_counts()?;
+        let counts = self.state_counts(CountMode::Exact);
+
+        let mut stats: BTreeMap<Position, SplitterStats> = self
+            .split_events
+            .iter()
+            .map(|(splitter, _)| {
+                let stats = SplitterStats {
+                    splitter: *splitter,
+                    timelines: BigUint::ZERO,
+                    sides: Vec::new(),
+                };
+                (*splitter, stats)
+            })
+            .collect();
+
+        for (state, next_states) in &self.next_states {
+            let Some(count) = counts.get(state) else {
+                continue;
+            };
+
+            for (next, choice) in next_states {
+                let (Some(choice), Some(exit_count)) = (choice, exit_counts.get(next)) else {
+                    continue;
+                };
+
+                // Timelines reaching the splitter from `state`, then leaving through `next`
+                let timelines = count * exit_count;
+
+                let stats = stats
+                    .get_mut(&choice.splitter)
+                    .expect("Split beams come from a splitter hit");
+                stats.timelines += &timelines;
+                match stats
+                    .sides
+                    .iter_mut()
+                    .find(|(side, _)| *side == choice.side)
+                {
+                    Some((_, side_count)) => *side_count += timelines,
+                    None => stats.sides.push((choice.side, timelines)),
                 }
             }
         }
+
+        Ok(stats.into_values().collect())
+    }
+
+    /// Number of timelines crossing each cell, `None` for cells without beams or with looping beams
+    fn cell_counts(&self, puzzle: &Puzzle) -> Vec<Vec<Option<BigUint>>> {
+        let mut counts: Vec<Vec<Option<BigUint>>> = puzzle
+            .manifold
+            .iter()
+            .map(|row| vec![None; row.len()])
+            .collect();
+
+        for (((i, j), _), count) in self.state_counts(CountMode::Exact) {
+            *counts[i][j].get_or_insert(BigUint::ZERO) += count;
+        }
+
+        for ((i, j), _) in &self.cyclic {
+            counts[*i][*j] = None;
+        }
+
+        counts
     }
+}
 
-    // Can print final manifold for visualisation and debugging
-    // println!("Final manifold:\n{}", puzzle);
-    // pretty_print_task_2_counts(&puzzle.manifold, &task_2_counts);
+/// Iterator over timelines, see [`Beams::enumerate`]
+pub struct Timelines<'a> {
+    beams: &'a Beams,
+    exit_counts: HashMap<State, BigUint>,
+    exits: HashSet<State>,
+    next_source: usize,
+    /// Path being explored: state, side taken to reach it, index of the next state to explore from it.
+    /// The index is `None` until the state is checked for leaving the manifold.
+    stack: Vec<(State, Option<Choice>, Option<usize>)>,
+}
 
-    let task_2 = task_2_counts.last().map(|c| c.iter().sum()).unwrap_or(0);
+impl Timelines<'_> {
+    fn has_timelines(&self, state: &State) -> bool {
+        self.exit_counts
+            .get(state)
+            .is_some_and(|count| *count > BigUint::ZERO)
+    }
 
-    Solution { task_1, task_2 }
+    fn current(&self) -> Timeline {
+        Timeline {
+            source: self.stack[0].0.0,
+            choices: self
+                .stack
+                .iter()
+                .filter_map(|(_, choice, _)| *choice)
+                .collect(),
+            exit: self.stack[self.stack.len() - 1].0.0,
+        }
+    }
 }
 
-fn _pretty_print_task_2_counts(final_manifold: &[Vec<Cell>], counts: &[Vec<usize>]) {
-    for i in 0..final_manifold.len() {
-        for j in 0..final_manifold[i].len() {
-            let cell = &final_manifold[i][j];
-            match cell {
-                Cell::Beam => print!("{}", counts[i][j]),
-                other => print!("{}", other),
+impl Iterator for Timelines<'_> {
+    type Item = Timeline;
+
+    fn next(&mut self) -> Option<Timeline> {
+        loop {
+            let Some((state, _, next_index)) = self.stack.last().copied() else {
+                // Start from the next source with timelines
+                let source = *self.beams.sources.get(self.next_source)?;
+                self.next_source += 1;
+                if self.has_timelines(&source) {
+                    self.stack.push((source, None, None));
+                }
+                continue;
+            };
+
+            let Some(next_index) = next_index else {
+                let last = self.stack.len() - 1;
+                self.stack[last].2 = Some(0);
+                if self.exits.contains(&state) {
+                    return Some(self.current());
+                }
+                continue;
+            };
+
+            let next_states = &self.beams.next_states[&state];
+            let next = (next_index..next_states.len())
+                .find(|&index| self.has_timelines(&next_states[index].0));
+
+            match next {
+                Some(index) => {
+                    let last = self.stack.len() - 1;
+                    self.stack[last].2 = Some(index + 1);
+                    let (next, choice) = next_states[index];
+                    self.stack.push((next, choice, None));
+                }
+                None => {
+                    self.stack.pop();
+                }
             }
         }
-        println!();
+    }
+}
+
+impl Puzzle {
+    fn cell(&self, (i, j): Position) -> Option<Cell> {
+        self.manifold.get(i).and_then(|row| row.get(j)).copied()
+    }
+
+    /// Neighbour of `position` in `direction`, if in the manifold
+    fn step(&self, (i, j): Position, direction: Direction) -> Option<Position> {
+        let (di, dj) = direction.offset();
+        let position = (i.checked_add_signed(di)?, j.checked_add_signed(dj)?);
+        self.cell(position).map(|_| position)
+    }
+
+    /// State of a beam entering `position`, `None` if it is absorbed
+    fn enter(&self, position: Position, direction: Direction) -> Option<State> {
+        match self.cell(position)? {
+            Cell::Empty | Cell::Source => Some((position, direction)),
+            mirror @ (Cell::MirrorSlash | Cell::MirrorBackslash) => {
+                Some((position, direction.reflect(mirror)))
+            }
+            Cell::Splitter | Cell::Wall => None,
+        }
+    }
+
+    /// States following `state` one step later. Records splits and beams leaving the manifold.
+    fn next_states(
+        &self,
+        (position, direction): State,
+        policy: EdgePolicy,
+        split_events: &mut HashSet<State>,
+        exits: &mut Vec<State>,
+    ) -> Result<Vec<(State, Option<Choice>)>, BeamError> {
+        let Some(next) = self.step(position, direction) else {
+            exits.push((position, direction));
+            return Ok(vec![]);
+        };
+
+        if self.cell(next) != Some(Cell::Splitter) {
+            return Ok(self
+                .enter(next, direction)
+                .map(|state| (state, None))
+                .into_iter()
+                .collect());
+        }
+
+        split_events.insert((next, direction));
+
+        // Beams continue on both sides of the splitter
+        let sides = direction.perpendicular();
+        let mut states = Vec::with_capacity(2);
+
+        for (k, side) in sides.iter().enumerate() {
+            let landing = match (self.step(next, *side), policy) {
+                (Some(landing), _) => landing,
+                (None, EdgePolicy::Lost) => continue,
+                (None, EdgePolicy::Reflected) => match self.step(next, sides[1 - k]) {
+                    Some(landing) => landing,
+                    // Both sides are outside, nowhere to bounce
+                    None => continue,
+                },
+                (None, EdgePolicy::Error) => {
+                    return Err(BeamError::SplitOutOfBounds {
+                        splitter: next,
+                        side: *side,
+                    });
+                }
+            };
+
+            if self.cell(landing) == Some(Cell::Splitter) {
+                return Err(BeamError::AdjacentSplitters {
+                    splitter: next,
+                    neighbour: landing,
+                });
+            }
+
+            if let Some(state) = self.enter(landing, direction) {
+                let choice = Choice {
+                    splitter: next,
+                    side: *side,
+                };
+                states.push((state, Some(choice)));
+            }
+        }
+
+        Ok(states)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
-    // Task 2 beam count visualisation:
-    // .......S.......
-    // .......1....... sum -> 1
-    // ......1^1...... sum -> 2
-    // ......1.1...... sum -> 2
-    // .....1^2^1..... sum -> 4
-    // .....1.2.1..... sum -> 4
-    // ....1^3^3^1.... sum -> 8
-    // ....1.3.3.1.... sum -> 8
-    // ...1^4^331^1... sum -> 13
-    // ...1.4.331.1... sum -> 13
-    // ..1^5^434^2^1.. sum -> 20
-    // ..1.5.434.2.1.. sum -> 20
-    // .1^154^74.21^1. sum -> 26
-    // .1.154.74.21.1. sum -> 26
-    // 1^2^a^b^b^211^1 sum -> 40
-    // ...............
+    // Task 2 beam count visualisation (`render_counts` with `CountsDisplay::FixedWidth`):
+    //  .  .  .  .  .  .  .  1  .  .  .  .  .  .  .
+    //  .  .  .  .  .  .  .  1  .  .  .  .  .  .  . sum -> 1
+    //  .  .  .  .  .  .  1  ^  1  .  .  .  .  .  . sum -> 2
+    //  .  .  .  .  .  .  1  .  1  .  .  .  .  .  . sum -> 2
+    //  .  .  .  .  .  1  ^  2  ^  1  .  .  .  .  . sum -> 4
+    //  .  .  .  .  .  1  .  2  .  1  .  .  .  .  . sum -> 4
+    //  .  .  .  .  1  ^  3  ^  3  ^  1  .  .  .  . sum -> 8
+    //  .  .  .  .  1  .  3  .  3  .  1  .  .  .  . sum -> 8
+    //  .  .  .  1  ^  4  ^  3  3  1  ^  1  .  .  . sum -> 13
+    //  .  .  .  1  .  4  .  3  3  1  .  1  .  .  . sum -> 13
+    //  .  .  1  ^  5  ^  4  3  4  ^  2  ^  1  .  . sum -> 20
+    //  .  .  1  .  5  .  4  3  4  .  2  .  1  .  . sum -> 20
+    //  .  1  ^  1  5  4  ^  7  4  .  2  1  ^  1  . sum -> 26
+    //  .  1  .  1  5  4  .  7  4  .  2  1  .  1  . sum -> 26
+    //  1  ^  2  ^ 10  ^ 11  ^ 11  ^  2  1  1  ^  1 sum -> 40
+    //  1  .  2  . 10  . 11  . 11  .  2  1  1  .  1 sum -> 40
 
     #[test]
     fn test_increment_example_1() {
@@ -204,7 +940,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 0);
         assert_eq!(solution.task_2, 1);
     }
@@ -219,7 +955,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 1);
         assert_eq!(solution.task_2, 2);
     }
@@ -236,7 +972,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 3);
         assert_eq!(solution.task_2, 4);
     }
@@ -255,7 +991,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 6);
         assert_eq!(solution.task_2, 8);
     }
@@ -276,7 +1012,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 9);
         assert_eq!(solution.task_2, 13);
     }
@@ -299,7 +1035,7 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 13);
         assert_eq!(solution.task_2, 20);
     }
@@ -324,16 +1060,427 @@ mod tests {
 "
         .trim();
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 16);
         assert_eq!(solution.task_2, 26);
     }
 
+    #[test]
+    fn test_timeline_grid() {
+        let input = "
+.S.
+...
+.^.
+...
+"
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+        let grid = timeline_grid(&puzzle, &Beams::trace(&puzzle).unwrap());
+        assert_eq!(
+            grid.cells,
+            vec![
+                vec!['.', '|', '.'],
+                vec!['.', '|', '.'],
+                vec!['|', '^', '|'],
+                vec!['|', '.', '|'],
+            ]
+        );
+        assert_eq!(
+            grid.values,
+            vec![
+                vec![None, Some(1), None],
+                vec![None, Some(1), None],
+                vec![Some(1), None, Some(1)],
+                vec![Some(1), None, Some(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirrors_and_walls() {
+        // Beam goes right, then up, then leaves on the top edge
+        let input = "
+S....
+.....
+\\../.
+";
+        let puzzle = parse_puzzle(input.trim_start()).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(beams.splits(), 0);
+        assert_eq!(beams.timelines().unwrap(), 1);
+        assert_eq!(beams.exits, vec![((0, 3), Direction::Up)]);
+
+        // Wall absorbs the beam
+        let puzzle = parse_puzzle("S\n#").unwrap();
+        assert_eq!(Beams::trace(&puzzle).unwrap().timelines().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_sideways_split() {
+        // Beam turns right and is split up and down, both beams leave on the right edge
+        let input = "
+S....
+\\.^..
+.....
+";
+        let puzzle = parse_puzzle(input.trim()).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(beams.splits(), 1);
+        assert_eq!(beams.timelines().unwrap(), 2);
+        assert_eq!(
+            timeline_grid(&puzzle, &beams).cells,
+            vec![
+                vec!['|', '.', '|', '|', '|'],
+                vec!['\\', '|', '^', '.', '.'],
+                vec!['.', '.', '|', '|', '|'],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_sources_merge() {
+        let input = "
+S.S
+...
+.^.
+...
+";
+        let puzzle = parse_puzzle(input.trim()).unwrap();
+        let solution = solve_puzzle(puzzle).unwrap();
+        // The middle splitter is not hit, beams from both sources continue down
+        assert_eq!(solution.task_1, 0);
+        assert_eq!(solution.task_2, 2);
+
+        let input = "
+.S.
+...
+.^.
+.S.
+";
+        let puzzle = parse_puzzle(input.trim()).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(beams.splits(), 1);
+        assert_eq!(beams.timelines().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_cycle() {
+        // Closed loop through the source: the beam never leaves, there is no timeline
+        let input = "
+/.\\
+S..
+\\./
+";
+        let puzzle = parse_puzzle(input.trim()).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert!(beams.has_cycle());
+        assert_eq!(beams.timelines().unwrap(), 0);
+
+        // The splitter sends a beam back into the loop and another one out: infinitely many timelines
+        let input = "
+/...\\
+S....
+..../
+\\.^..
+.....
+";
+        let puzzle = parse_puzzle(input.trim()).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert!(beams.has_cycle());
+        assert_eq!(beams.splits(), 1);
+        assert!(beams.timelines().is_err());
+        assert!(solve_puzzle(puzzle).is_err());
+    }
+
+    /// Full triangle of splitters: every beam is split at each level
+    fn pascal_triangle(levels: usize) -> String {
+        let width = 2 * levels + 3;
+        let center = levels + 1;
+        let mut lines = vec![format!("{}S{}", ".".repeat(center), ".".repeat(center))];
+
+        for level in 0..levels {
+            lines.push(".".repeat(width));
+            let mut line = vec!['.'; width];
+            for m in 0..=level {
+                line[center - level + 2 * m] = '^';
+            }
+            lines.push(line.into_iter().collect());
+        }
+        lines.push(".".repeat(width));
+
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_big_timeline_counts() {
+        let puzzle = parse_puzzle(&pascal_triangle(70)).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(beams.splits(), 70 * 71 / 2);
+
+        let expected = BigUint::from(2u32).pow(70);
+        assert_eq!(beams.timeline_count(CountMode::Exact).unwrap(), expected);
+        assert_eq!(
+            beams
+                .timeline_count(CountMode::Modulo(1_000_000_007))
+                .unwrap(),
+            &expected % 1_000_000_007u32
+        );
+        assert!(beams.timelines().is_err());
+        assert!(beams.timeline_count(CountMode::Modulo(0)).is_err());
+    }
+
+    #[test]
+    fn test_modulo_counts() {
+        let input = include_str!("../inputs/examples/day_7");
+        let beams = Beams::trace(&parse_puzzle(input).unwrap()).unwrap();
+        assert_eq!(
+            beams.timeline_count(CountMode::Modulo(7)).unwrap(),
+            BigUint::from(40u32 % 7)
+        );
+        assert_eq!(
+            beams.timeline_count(CountMode::Modulo(1)).unwrap(),
+            BigUint::ZERO
+        );
+    }
+
+    #[test]
+    fn test_enumerate_timelines() {
+        let input = "
+.S.
+...
+.^.
+...
+"
+        .trim();
+        let beams = Beams::trace(&parse_puzzle(input).unwrap()).unwrap();
+        let timelines: Vec<Timeline> = beams.enumerate().unwrap().collect();
+        assert_eq!(
+            timelines,
+            vec![
+                Timeline {
+                    source: (0, 1),
+                    choices: vec![Choice {
+                        splitter: (2, 1),
+                        side: Direction::Left
+                    }],
+                    exit: (3, 0),
+                },
+                Timeline {
+                    source: (0, 1),
+                    choices: vec![Choice {
+                        splitter: (2, 1),
+                        side: Direction::Right
+                    }],
+                    exit: (3, 2),
+                },
+            ]
+        );
+        assert_eq!(
+            timelines[0].to_string(),
+            "(0, 1) Left at (2, 1), leaves at (3, 0)"
+        );
+
+        let input = include_str!("../inputs/examples/day_7");
+        let beams = Beams::trace(&parse_puzzle(input).unwrap()).unwrap();
+        let timelines: HashSet<Timeline> = beams.enumerate().unwrap().collect();
+        assert_eq!(timelines.len(), 40);
+
+        // Lost and absorbed beams are not timelines
+        let beams = Beams::trace(&parse_puzzle("S.\n^#").unwrap()).unwrap();
+        assert_eq!(beams.enumerate().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_sample_timelines() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let input = include_str!("../inputs/examples/day_7");
+        let beams = Beams::trace(&parse_puzzle(input).unwrap()).unwrap();
+        let timelines: HashSet<Timeline> = beams.enumerate().unwrap().collect();
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut sampled: HashMap<Timeline, usize> = HashMap::new();
+        for _ in 0..4000 {
+            let timeline = beams.sample(&mut rng).unwrap().unwrap();
+            assert!(timelines.contains(&timeline));
+            *sampled.entry(timeline).or_default() += 1;
+        }
+
+        // Uniform: each of the 40 timelines is sampled about 100 times
+        assert_eq!(sampled.len(), 40);
+        assert!(sampled.values().all(|&count| (50..150).contains(&count)));
+
+        let beams = Beams::trace(&parse_puzzle("S\n#").unwrap()).unwrap();
+        assert_eq!(beams.sample(&mut rng).unwrap(), None);
+    }
+
+    #[test]
+    fn test_splitter_stats() {
+        let input = include_str!("../inputs/examples/day_7");
+        let beams = Beams::trace(&parse_puzzle(input).unwrap()).unwrap();
+        let stats = beams.splitter_stats().unwrap();
+        assert_eq!(stats.len(), 21);
+
+        // All timelines go through the first splitter
+        assert_eq!(
+            stats[0],
+            SplitterStats {
+                splitter: (2, 7),
+                timelines: BigUint::from(40u32),
+                sides: vec![
+                    (Direction::Left, BigUint::from(25u32)),
+                    (Direction::Right, BigUint::from(15u32)),
+                ],
+            }
+        );
+        assert_eq!(
+            stats[0].to_string(),
+            "(2, 7): 40 timelines, 25 Left, 15 Right"
+        );
+
+        let timelines: Vec<Timeline> = beams.enumerate().unwrap().collect();
+        for stat in &stats {
+            let expected = timelines
+                .iter()
+                .filter(|t| t.choices.iter().any(|c| c.splitter == stat.splitter))
+                .count();
+            assert_eq!(stat.timelines, BigUint::from(expected));
+        }
+    }
+
+    #[test]
+    fn test_splitter_at_edge() {
+        let puzzle = parse_puzzle("S.\n^.\n..").unwrap();
+
+        let beams = Beams::trace_with_policy(&puzzle, EdgePolicy::Lost).unwrap();
+        assert_eq!(beams.splits(), 1);
+        assert_eq!(beams.timelines().unwrap(), 1);
+
+        // Both timelines end up on the right of the splitter
+        let beams = Beams::trace_with_policy(&puzzle, EdgePolicy::Reflected).unwrap();
+        assert_eq!(beams.timelines().unwrap(), 2);
+        let sides: Vec<Direction> = beams
+            .enumerate()
+            .unwrap()
+            .map(|timeline| timeline.choices[0].side)
+            .collect();
+        assert_eq!(sides, vec![Direction::Left, Direction::Right]);
+
+        let error = Beams::trace_with_policy(&puzzle, EdgePolicy::Error).err();
+        assert_eq!(
+            error,
+            Some(BeamError::SplitOutOfBounds {
+                splitter: (1, 0),
+                side: Direction::Left
+            })
+        );
+        assert_eq!(
+            error.unwrap().to_string(),
+            "Splitter at (1, 0) sends a beam Left outside of the manifold"
+        );
+
+        // Nowhere to bounce in a single column
+        let puzzle = parse_puzzle("S\n^\n.").unwrap();
+        let beams = Beams::trace_with_policy(&puzzle, EdgePolicy::Reflected).unwrap();
+        assert_eq!(beams.timelines().unwrap(), 0);
+
+        let solution =
+            solve_puzzle_with_policy(parse_puzzle("S.\n^.\n..").unwrap(), EdgePolicy::Reflected)
+                .unwrap();
+        assert_eq!(solution.task_2, 2);
+        assert!(
+            solve_puzzle_with_policy(parse_puzzle("S.\n^.\n..").unwrap(), EdgePolicy::Error)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_adjacent_splitters() {
+        let input = "
+.S..
+.^^.
+....
+"
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+        assert_eq!(
+            Beams::trace(&puzzle).err(),
+            Some(BeamError::AdjacentSplitters {
+                splitter: (1, 1),
+                neighbour: (1, 2)
+            })
+        );
+        assert_eq!(
+            solve_puzzle(puzzle).unwrap_err().to_string(),
+            "Splitter at (1, 1) sends a beam into the splitter at (1, 2)"
+        );
+    }
+
+    #[test]
+    fn test_render_counts() {
+        let input = "
+.S.
+...
+.^.
+...
+"
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(
+            render_counts(&puzzle, &beams, CountsDisplay::FixedWidth),
+            ". 1 .\n. 1 .\n1 ^ 1\n1 . 1\n"
+        );
+
+        // Two digits counts keep the grid aligned
+        let input = include_str!("../inputs/examples/day_7");
+        let example = parse_puzzle(input).unwrap();
+        let rendered = render_counts(
+            &example,
+            &Beams::trace(&example).unwrap(),
+            CountsDisplay::FixedWidth,
+        );
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines.iter().all(|line| line.len() == 15 * 3 - 1));
+        assert_eq!(lines[14], " 1  ^  2  ^ 10  ^ 11  ^ 11  ^  2  1  1  ^  1");
+
+        let coloured = render_counts(&puzzle, &beams, CountsDisplay::Colour(HeatmapScale::Log));
+        // All beam cells have a single timeline: same colour
+        assert!(coloured.starts_with(".\x1b[38;2;255;230;40m|\x1b[0m.\n"));
+        assert_eq!(coloured.matches("\x1b[38;2;255;230;40m").count(), 6);
+    }
+
+    #[test]
+    fn test_counts_csv() {
+        let input = "
+.S.
+...
+.^.
+...
+"
+        .trim();
+        let puzzle = parse_puzzle(input).unwrap();
+        let beams = Beams::trace(&puzzle).unwrap();
+        assert_eq!(counts_csv(&puzzle, &beams), ",1,\n,1,\n1,,1\n1,,1\n");
+
+        let puzzle = parse_puzzle(&pascal_triangle(70)).unwrap();
+        let csv = counts_csv(&puzzle, &Beams::trace(&puzzle).unwrap());
+        let last_row: BigUint = csv
+            .lines()
+            .last()
+            .unwrap()
+            .split(',')
+            .filter(|count| !count.is_empty())
+            .map(|count| count.parse::<BigUint>().unwrap())
+            .sum();
+        assert_eq!(last_row, BigUint::from(2u32).pow(70));
+    }
+
     #[test]
     fn test_example_puzzle() {
         let input = include_str!("../inputs/examples/day_7");
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 21);
         assert_eq!(solution.task_2, 40);
     }
@@ -342,8 +1489,71 @@ mod tests {
     fn test_real_input() {
         let input = include_str!("../inputs/day_7");
         let puzzle = parse_puzzle(input).unwrap();
-        let solution = solve_puzzle(puzzle);
+        let solution = solve_puzzle(puzzle).unwrap();
         assert_eq!(solution.task_1, 1626);
         assert_eq!(solution.task_2, 48989920237096);
     }
+
+    /// Reference implementation: follows every timeline of a beam going down from (i, j).
+    /// Returns the number of timelines leaving the manifold and records the splitters reached.
+    fn timelines_naive(
+        manifold: &[Vec<char>],
+        (i, j): (usize, usize),
+        splitters: &mut HashSet<(usize, usize)>,
+    ) -> usize {
+        let Some(row) = manifold.get(i + 1) else {
+            return 1;
+        };
+
+        match row[j] {
+            '#' => 0,
+            '^' => {
+                splitters.insert((i + 1, j));
+                [j.checked_sub(1), Some(j + 1)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|&side| side < row.len() && row[side] != '#')
+                    .map(|side| timelines_naive(manifold, (i + 1, side), splitters))
+                    .sum()
+            }
+            _ => timelines_naive(manifold, (i + 1, j), splitters),
+        }
+    }
+
+    /// Manifold with a source in the first row, and splitters that are never side by side
+    fn manifold() -> impl Strategy<Value = Vec<Vec<char>>> {
+        (1..8usize, 1..10usize).prop_flat_map(|(width, height)| {
+            let cell = prop::sample::select(vec!['.', '.', '^', '#']);
+            let rows = prop::collection::vec(prop::collection::vec(cell, width), height);
+            (0..width, rows).prop_map(move |(source, mut rows)| {
+                for row in rows.iter_mut() {
+                    for j in 1..width {
+                        if row[j - 1] == '^' && row[j] == '^' {
+                            row[j] = '.';
+                        }
+                    }
+                }
+                rows[0] = vec!['.'; width];
+                rows[0][source] = 'S';
+                rows
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(manifold in manifold()) {
+            let input: Vec<String> = manifold.iter().map(|row| row.iter().collect()).collect();
+            let puzzle = parse_puzzle(&input.join("\n")).unwrap();
+            let beams = Beams::trace(&puzzle).unwrap();
+
+            let source = manifold[0].iter().position(|&c| c == 'S').unwrap();
+            let mut splitters = HashSet::new();
+            let expected = timelines_naive(&manifold, (0, source), &mut splitters);
+
+            prop_assert_eq!(beams.splits(), splitters.len());
+            prop_assert_eq!(beams.timelines().unwrap(), expected);
+            prop_assert_eq!(beams.enumerate().unwrap().count(), expected);
+        }
+    }
 }
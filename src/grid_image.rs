@@ -0,0 +1,386 @@
+use anyhow::{Context, Result, anyhow};
+use std::{fs::File, io::BufWriter, path::Path};
+
+pub type Rgb = (u8, u8, u8);
+
+/// Grid to export as an image
+pub struct Grid {
+    /// Type of each cell, as its character in the puzzle (coloured with the palette)
+    pub cells: Vec<Vec<char>>,
+    /// Value of each cell, shown as label or heatmap
+    pub values: Vec<Vec<Option<usize>>>,
+}
+
+/// How values are mapped to heatmap colours
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeatmapScale {
+    Linear,
+    /// Logarithmic, for values spanning several orders of magnitude
+    Log,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageOptions {
+    /// Size of a cell in pixels
+    pub cell_size: usize,
+    /// Colour of each cell type, unknown types are grey
+    pub palette: Vec<(char, Rgb)>,
+    /// Write values on cells having one
+    pub labels: bool,
+    /// Colour cells having a value by their value instead of their type
+    pub heatmap: Option<HeatmapScale>,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            cell_size: 16,
+            palette: vec![
+                ('.', (30, 30, 30)),
+                ('@', (200, 200, 200)),
+                ('S', (60, 200, 60)),
+                ('|', (80, 160, 255)),
+                ('^', (220, 60, 60)),
+                ('/', (240, 160, 40)),
+                ('\\', (240, 160, 40)),
+                ('#', (110, 80, 50)),
+            ],
+            labels: false,
+            heatmap: None,
+        }
+    }
+}
+
+const UNKNOWN_COLOUR: Rgb = (128, 128, 128);
+
+impl Grid {
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn width(&self) -> usize {
+        self.cells.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn value(&self, x: usize, y: usize) -> Option<usize> {
+        self.values.get(x).and_then(|row| row.get(y)).copied()?
+    }
+
+    /// Colour of each cell
+    fn colours(&self, options: &ImageOptions) -> Vec<Vec<Rgb>> {
+        let values = self.values.iter().flatten().flatten();
+        let min = values.clone().min().copied().unwrap_or(0);
+        let max = values.max().copied().unwrap_or(0);
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(x, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(y, cell)| match (options.heatmap, self.value(x, y)) {
+                        (Some(scale), Some(value)) => heat_colour(value, min, max, scale),
+                        _ => options
+                            .palette
+                            .iter()
+                            .find(|(c, _)| c == cell)
+                            .map(|(_, colour)| *colour)
+                            .unwrap_or(UNKNOWN_COLOUR),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn to_svg(&self, options: &ImageOptions) -> String {
+        let size = options.cell_size;
+        let colours = self.colours(options);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            self.width() * size,
+            self.height() * size
+        );
+
+        for (x, row) in colours.iter().enumerate() {
+            for (y, colour) in row.iter().enumerate() {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    y * size,
+                    x * size,
+                    size,
+                    size,
+                    hex(*colour)
+                ));
+
+                if options.labels
+                    && let Some(value) = self.value(x, y)
+                {
+                    let label = value.to_string();
+                    // Shrink font for long labels so they fit in the cell
+                    let font_size = (size as f64 * 0.7).min(size as f64 * 1.6 / label.len() as f64);
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" font-size=\"{:.1}\" font-family=\"monospace\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>\n",
+                        y * size + size / 2,
+                        x * size + size / 2,
+                        font_size,
+                        hex(label_colour(*colour)),
+                        label
+                    ));
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_svg(&self, path: &Path, options: &ImageOptions) -> Result<()> {
+        std::fs::write(path, self.to_svg(options))
+            .with_context(|| format!("Failed to write file {}", path.display()))
+    }
+
+    /// RGB pixels, row by row
+    fn to_pixels(&self, options: &ImageOptions) -> Vec<u8> {
+        let size = options.cell_size;
+        let image_width = self.width() * size;
+        let colours = self.colours(options);
+
+        let mut pixels = vec![0; image_width * self.height() * size * 3];
+
+        let mut set_pixel = |i: usize, j: usize, (r, g, b): Rgb| {
+            let index = (i * image_width + j) * 3;
+            pixels[index..index + 3].copy_from_slice(&[r, g, b]);
+        };
+
+        for (x, row) in colours.iter().enumerate() {
+            for (y, colour) in row.iter().enumerate() {
+                for i in 0..size {
+                    for j in 0..size {
+                        set_pixel(x * size + i, y * size + j, *colour);
+                    }
+                }
+
+                if options.labels
+                    && let Some(value) = self.value(x, y)
+                {
+                    for (i, j) in label_pixels(&value.to_string(), size) {
+                        set_pixel(x * size + i, y * size + j, label_colour(*colour));
+                    }
+                }
+            }
+        }
+
+        pixels
+    }
+
+    pub fn write_png(&self, path: &Path, options: &ImageOptions) -> Result<()> {
+        let too_large = || anyhow!("Image is too large for PNG");
+        let width = self
+            .width()
+            .checked_mul(options.cell_size)
+            .and_then(|width| u32::try_from(width).ok())
+            .ok_or_else(too_large)?;
+        let height = self
+            .height()
+            .checked_mul(options.cell_size)
+            .and_then(|height| u32::try_from(height).ok())
+            .ok_or_else(too_large)?;
+
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Cannot write an empty grid as PNG"));
+        }
+
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create file {}", path.display()))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_pixels(options))?;
+
+        Ok(())
+    }
+}
+
+/// Colour from dark blue (`min`) to yellow (`max`)
+pub(crate) fn heat_colour(value: usize, min: usize, max: usize, scale: HeatmapScale) -> Rgb {
+    let t = if max == min {
+        1.0
+    } else {
+        let offset = (value - min) as f64;
+        let range = (max - min) as f64;
+        match scale {
+            HeatmapScale::Linear => offset / range,
+            HeatmapScale::Log => offset.ln_1p() / range.ln_1p(),
+        }
+    };
+
+    let lerp = |from: f64, to: f64| (from + (to - from) * t).round() as u8;
+
+    (lerp(20.0, 255.0), lerp(30.0, 230.0), lerp(120.0, 40.0))
+}
+
+/// Black or white, whichever is more readable on a cell of this colour
+fn label_colour((r, g, b): Rgb) -> Rgb {
+    let luminance = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+    if luminance > 128.0 {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    }
+}
+
+fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// 3x5 bitmap font for digits, one row per `u8` (3 least significant bits, left to right)
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Pixels (row, column) of `label` centered in a cell of `size` pixels.
+/// Empty if the label does not fit in the cell.
+fn label_pixels(label: &str, size: usize) -> Vec<(usize, usize)> {
+    // Each digit is 3 pixels wide, followed by 1 pixel of spacing
+    let label_width = label.len() * 4 - 1;
+    let scale = (size.saturating_sub(2) / label_width).min(size.saturating_sub(2) / 5);
+
+    if scale == 0 {
+        return vec![];
+    }
+
+    let top = (size - 5 * scale) / 2;
+    let left = (size - label_width * scale) / 2;
+
+    let mut pixels = Vec::new();
+
+    for (k, digit) in label.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+        for (i, bits) in DIGITS[digit as usize].iter().enumerate() {
+            for j in 0..3 {
+                if bits & (0b100 >> j) != 0 {
+                    for di in 0..scale {
+                        for dj in 0..scale {
+                            pixels.push((top + i * scale + di, left + (k * 4 + j) * scale + dj));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid {
+            cells: vec![vec!['.', '@'], vec!['@', 'x']],
+            values: vec![vec![None, Some(1)], vec![Some(100), None]],
+        }
+    }
+
+    #[test]
+    fn test_colours() {
+        let options = ImageOptions::default();
+        assert_eq!(
+            grid().colours(&options),
+            vec![
+                vec![(30, 30, 30), (200, 200, 200)],
+                vec![(200, 200, 200), UNKNOWN_COLOUR],
+            ]
+        );
+
+        let options = ImageOptions {
+            heatmap: Some(HeatmapScale::Linear),
+            ..ImageOptions::default()
+        };
+        assert_eq!(
+            grid().colours(&options),
+            vec![
+                vec![(30, 30, 30), (20, 30, 120)],
+                vec![(255, 230, 40), UNKNOWN_COLOUR],
+            ]
+        );
+
+        // Day 7 mirrors and walls
+        let grid = Grid {
+            cells: vec![vec!['/', '\\', '#']],
+            values: vec![vec![None; 3]],
+        };
+        assert_eq!(
+            grid.colours(&ImageOptions::default()),
+            vec![vec![(240, 160, 40), (240, 160, 40), (110, 80, 50)]]
+        );
+    }
+
+    #[test]
+    fn test_heat_colour_log() {
+        let linear = heat_colour(10, 0, 1000, HeatmapScale::Linear);
+        let log = heat_colour(10, 0, 1000, HeatmapScale::Log);
+        // Small values are brighter in log scale
+        assert!(log.0 > linear.0);
+        assert_eq!(heat_colour(5, 5, 5, HeatmapScale::Log), (255, 230, 40));
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let options = ImageOptions {
+            cell_size: 10,
+            labels: true,
+            ..ImageOptions::default()
+        };
+        let svg = grid().to_svg(&options);
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\"")
+        );
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert_eq!(svg.matches("<text").count(), 2);
+        assert!(svg.contains(">100</text>"));
+    }
+
+    #[test]
+    fn test_label_pixels() {
+        // "1" scaled x2 in a 12 pixels cell: 8 lit pixels in the font
+        assert_eq!(label_pixels("1", 12).len(), 8 * 4);
+        // Does not fit
+        assert!(label_pixels("12345", 8).is_empty());
+    }
+
+    #[test]
+    fn test_write_png() {
+        // Unique per process, for parallel test runs
+        let path = std::env::temp_dir().join(format!(
+            "aoc_2025_grid_image_test_{}.png",
+            std::process::id()
+        ));
+        grid().write_png(&path, &ImageOptions::default()).unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        assert!(content.starts_with(b"\x89PNG"));
+        std::fs::remove_file(&path).unwrap();
+
+        let options = ImageOptions {
+            cell_size: usize::MAX,
+            ..ImageOptions::default()
+        };
+        let error = grid().write_png(&path, &options).unwrap_err();
+        assert_eq!(error.to_string(), "Image is too large for PNG");
+        assert!(!path.exists());
+    }
+}
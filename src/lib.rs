@@ -6,4 +6,6 @@ pub mod day_4;
 pub mod day_5;
 pub mod day_6;
 pub mod day_7;
+pub mod grid_image;
 pub mod range;
+pub mod subsequence;
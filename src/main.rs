@@ -1,18 +1,24 @@
-use anyhow::{Result, anyhow};
-use aoc_2025::common;
-use std::env;
+use anyhow::{Context, Result, anyhow};
+use aoc_2025::common::{self, IntoSolution};
+use aoc_2025::grid_image::{Grid, HeatmapScale, ImageOptions};
+use std::{env, path::PathBuf};
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 {
+    if args.len() < 2 {
         return Err(anyhow!(
-            "Expected two arguments - the program and the day to run (day_1, ..., day_12). Got {} arguments.",
+            "Expected at least two arguments - the program and the day to run (day_1, ..., day_12), optionally followed by options. Got {} arguments.",
             args.len()
         ));
     }
 
     let day = args[1].as_str();
+    let options = &args[2..];
+
+    if !options.is_empty() {
+        return run_day_with_options(day, options);
+    }
 
     match day {
         "day_1" => run_day!(day_1),
@@ -29,8 +35,518 @@ fn main() -> Result<()> {
     }
 }
 
+/// Days accepting options on top of the default run
+fn run_day_with_options(day: &str, options: &[String]) -> Result<()> {
+    match day {
+        "day_3" => run_day_3(options),
+        "day_4" => run_day_4(options),
+        "day_5" => run_day_5(options),
+        "day_6" => run_day_6(options),
+        "day_7" => run_day_7(options),
+        other => Err(anyhow!(
+            "{} does not take options, got {:?}",
+            other,
+            options
+        )),
+    }
+}
+
+/// Options:
+/// - `--batteries 2,5,12,20`: total joltage for each number of batteries
+/// - `--show N`: render each bank with the N selected batteries highlighted
+/// - `--ties`: with `--show`, render the first optimal selections of each bank and count the others
+fn run_day_3(options: &[String]) -> Result<()> {
+    use aoc_2025::day_3::{
+        ShortBankPolicy, parse_battery_counts, render_selections, solve_puzzle_counts,
+    };
+
+    /// Optimal selections rendered per bank with `--ties`, there can be millions
+    const MAX_TIES: usize = 10;
+
+    let mut counts: Option<Vec<usize>> = None;
+    let mut show: Option<usize> = None;
+    let mut all_ties = false;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--batteries" => {
+                let value = options.next().context("Missing value for --batteries")?;
+                counts = Some(parse_battery_counts(value)?);
+            }
+            "--show" => {
+                let value = options.next().context("Missing value for --show")?;
+                show = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid value for --show: '{}'", value))?,
+                );
+            }
+            "--ties" => all_ties = true,
+            other => return Err(anyhow!("Unknown option for day_3: '{}'", other)),
+        }
+    }
+
+    if counts.is_none() && show.is_none() {
+        return Err(anyhow!("Expected --batteries or --show option for day_3"));
+    }
+
+    let puzzle = parse_day!(day_3);
+
+    if let Some(counts) = counts {
+        let totals = solve_puzzle_counts(&puzzle, &counts, ShortBankPolicy::WholeBank)?;
+        println!("Solution:");
+        for (count, total) in counts.iter().zip(totals) {
+            println!("- {} batteries: {}", count, total);
+        }
+    }
+
+    if let Some(num_on_batteries) = show {
+        print!(
+            "{}",
+            render_selections(
+                &puzzle,
+                num_on_batteries,
+                ShortBankPolicy::WholeBank,
+                all_ties.then_some(MAX_TIES)
+            )?
+        );
+    }
+
+    Ok(())
+}
+
+/// Image export options, for days with a grid
+#[derive(Default)]
+struct ImageExport {
+    svg: Option<PathBuf>,
+    png: Option<PathBuf>,
+    options: ImageOptions,
+}
+
+impl ImageExport {
+    /// Options:
+    /// - `--svg PATH`, `--png PATH`: export the grid as SVG or PNG
+    /// - `--labels`: write values on cells
+    /// - `--heatmap linear|log`: colour cells by value
+    ///
+    /// Returns whether `option` is an image option, consuming its value from `options`
+    fn parse_option<'a>(
+        &mut self,
+        option: &str,
+        options: &mut impl Iterator<Item = &'a String>,
+    ) -> Result<bool> {
+        match option {
+            "--svg" => self.svg = Some(options.next().context("Missing value for --svg")?.into()),
+            "--png" => self.png = Some(options.next().context("Missing value for --png")?.into()),
+            "--labels" => self.options.labels = true,
+            "--heatmap" => {
+                let value = options.next().context("Missing value for --heatmap")?;
+                self.options.heatmap = Some(match value.as_str() {
+                    "linear" => HeatmapScale::Linear,
+                    "log" => HeatmapScale::Log,
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid value for --heatmap: '{}'. Expected 'linear' or 'log'",
+                            other
+                        ));
+                    }
+                });
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Whether an image file is requested
+    fn is_set(&self) -> bool {
+        self.svg.is_some() || self.png.is_some()
+    }
+
+    fn export(&self, grid: &Grid) -> Result<()> {
+        if let Some(path) = &self.svg {
+            grid.write_svg(path, &self.options)?;
+            println!("Wrote {}", path.display());
+        }
+        if let Some(path) = &self.png {
+            grid.write_png(path, &self.options)?;
+            println!("Wrote {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Options:
+/// - `--animate`: print the diagram after each removal round in the terminal
+/// - `--gif PATH`: write the removal rounds as an animated GIF
+/// - `--delay MS`: delay between frames, 200ms by default (10ms to 655350ms with `--gif`)
+/// - Image options (see [`ImageExport`]), values are removal rounds
+/// - `--threshold N`: papers with fewer than N adjacent papers are accessible, 4 by default
+/// - `--neighbourhood von-neumann|moore|radius:R`: positions adjacent to a paper, moore by default
+/// - `--edge empty|paper|wrap`: what lies outside of the diagram, empty by default.
+///   Prints the solution when no other option is given.
+fn run_day_4(options: &[String]) -> Result<()> {
+    use aoc_2025::day_4::{Edge, Neighbourhood, Rule, removal_history};
+    use std::time::Duration;
+
+    /// Side of the square drawn for each cell in GIF frames, in pixels
+    const GIF_CELL_SIZE: usize = 4;
+
+    let mut image = ImageExport::default();
+    let mut animate = false;
+    let mut gif: Option<PathBuf> = None;
+    let mut delay = Duration::from_millis(200);
+    let mut rule = Rule::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--animate" => animate = true,
+            "--gif" => gif = Some(options.next().context("Missing value for --gif")?.into()),
+            "--delay" => {
+                let value = options.next().context("Missing value for --delay")?;
+                delay = Duration::from_millis(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid value for --delay: '{}'", value))?,
+                );
+            }
+            "--threshold" => {
+                let value = options.next().context("Missing value for --threshold")?;
+                rule.threshold = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --threshold: '{}'", value))?;
+            }
+            "--neighbourhood" => {
+                let value = options
+                    .next()
+                    .context("Missing value for --neighbourhood")?;
+                rule.neighbourhood = match value.as_str() {
+                    "von-neumann" => Neighbourhood::VonNeumann,
+                    "moore" => Neighbourhood::Moore,
+                    other => match other.strip_prefix("radius:").map(str::parse) {
+                        Some(Ok(radius)) => Neighbourhood::Radius(radius),
+                        _ => {
+                            return Err(anyhow!(
+                                "Invalid value for --neighbourhood: '{}'. Expected 'von-neumann', 'moore' or 'radius:R'",
+                                other
+                            ));
+                        }
+                    },
+                };
+            }
+            "--edge" => {
+                let value = options.next().context("Missing value for --edge")?;
+                rule.edge = match value.as_str() {
+                    "empty" => Edge::Empty,
+                    "paper" => Edge::Paper,
+                    "wrap" => Edge::Wrap,
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid value for --edge: '{}'. Expected 'empty', 'paper' or 'wrap'",
+                            other
+                        ));
+                    }
+                };
+            }
+            other if image.parse_option(other, &mut options)? => (),
+            other => return Err(anyhow!("Unknown option for day_4: '{}'", other)),
+        }
+    }
+
+    let puzzle = parse_day!(day_4);
+    let history = removal_history(puzzle, &rule);
+
+    if !animate && gif.is_none() && !image.is_set() {
+        let rounds = history.rounds();
+        println!(
+            "Solution:\n- Task 1: {}\n- Task 2: {}",
+            rounds.first().map_or(0, Vec::len),
+            rounds.iter().map(Vec::len).sum::<usize>()
+        );
+    }
+
+    if animate {
+        history.animate(delay);
+    }
+
+    if let Some(path) = gif {
+        history.write_gif(&path, GIF_CELL_SIZE, delay)?;
+        println!("Wrote {}", path.display());
+    }
+
+    image.export(&history.grid())
+}
+
+/// Options:
+/// - `--explain ID`: which ranges make ingredient ID fresh (can be repeated)
+/// - `--spoiled`: list spoiled available ingredients with the nearest fresh range
+fn run_day_5(options: &[String]) -> Result<()> {
+    use aoc_2025::day_5::{explain, spoiled_report};
+
+    let mut ids: Vec<usize> = Vec::new();
+    let mut spoiled = false;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--explain" => {
+                let value = options.next().context("Missing value for --explain")?;
+                ids.push(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid value for --explain: '{}'", value))?,
+                );
+            }
+            "--spoiled" => spoiled = true,
+            other => return Err(anyhow!("Unknown option for day_5: '{}'", other)),
+        }
+    }
+
+    let puzzle = parse_day!(day_5);
+
+    for id in ids {
+        println!("{}", explain(&puzzle, id));
+    }
+
+    if spoiled {
+        let report = spoiled_report(&puzzle);
+        println!("{} spoiled ingredients:", report.len());
+        for spoiled in report {
+            println!("- {}", spoiled);
+        }
+    }
+
+    Ok(())
+}
+
+/// Options:
+/// - `--worksheet`: render each problem with its row and column readings
+/// - `--side-by-side`: with `--worksheet`, render both readings next to each other
+/// - `--operators-top`: operator rows are above the digits of each block
+/// - `--check`: compare results with the result rows of the worksheet
+/// - `--tab-width N`: tabs expand to the next multiple of N columns (8 by default, at most 64)
+fn run_day_6(options: &[String]) -> Result<()> {
+    use aoc_2025::day_6::{
+        MAX_TAB_WIDTH, OperatorRow, ParseOptions, WorksheetLayout, mismatches,
+        parse_puzzle_with_options, render_worksheet,
+    };
+
+    let mut worksheet = false;
+    let mut layout = WorksheetLayout::Stacked;
+    let mut check = false;
+    let mut parse_options = ParseOptions::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--worksheet" => worksheet = true,
+            "--side-by-side" => layout = WorksheetLayout::SideBySide,
+            "--operators-top" => parse_options.operator_row = OperatorRow::Top,
+            "--check" => check = true,
+            "--tab-width" => {
+                let value = options.next().context("Missing value for --tab-width")?;
+                parse_options.tab_width = value
+                    .parse()
+                    .ok()
+                    .filter(|tab_width| (1..=MAX_TAB_WIDTH).contains(tab_width))
+                    .with_context(|| {
+                        format!(
+                            "Invalid value for --tab-width: '{}'. Expected a number between 1 and {}",
+                            value, MAX_TAB_WIDTH
+                        )
+                    })?;
+            }
+            other => return Err(anyhow!("Unknown option for day_6: '{}'", other)),
+        }
+    }
+
+    if layout == WorksheetLayout::SideBySide && !worksheet {
+        return Err(anyhow!("--side-by-side requires --worksheet"));
+    }
+
+    let input = common::read_input("day_6")
+        .map_err(|e| anyhow!("Error reading puzzle for day_6: {}", e))?;
+    let puzzle = parse_puzzle_with_options(&input, &parse_options)
+        .map_err(|e| anyhow!("Error parsing puzzle for day_6: {}", e))?;
+
+    if worksheet {
+        print!("{}", render_worksheet(&puzzle, layout));
+    }
+
+    if check {
+        let mismatches = mismatches(&puzzle)?;
+        println!("{} mismatching results", mismatches.len());
+        for mismatch in mismatches {
+            println!("- {}", mismatch);
+        }
+    }
+
+    if !worksheet && !check {
+        let solution = aoc_2025::day_6::solve_puzzle(puzzle)?;
+        println!(
+            "Solution:\n- Task 1: {}\n- Task 2: {}",
+            solution.task_1, solution.task_2,
+        );
+    }
+
+    Ok(())
+}
+
+/// Options:
+/// - image options (see [`ImageExport`]), values are timeline counts
+/// - `--exact`: number of timelines without overflow
+/// - `--modulo P`: number of timelines modulo P
+/// - `--paths N`: list the first N timelines
+/// - `--sample N`: list N timelines picked uniformly at random
+/// - `--splitters`: number of timelines going through each splitter
+/// - `--counts fixed|linear|log`: print the number of timelines crossing each cell, as numbers or colours
+/// - `--csv PATH`: write the number of timelines crossing each cell as CSV
+/// - `--edge lost|reflected|error`: what happens to split beams landing outside of the manifold, lost by default.
+///   Prints the solution when no other option is given.
+fn run_day_7(options: &[String]) -> Result<()> {
+    use aoc_2025::day_7::{
+        Beams, CountMode, CountsDisplay, EdgePolicy, counts_csv, render_counts, timeline_grid,
+    };
+
+    let mut image = ImageExport::default();
+    let mut count_mode: Option<CountMode> = None;
+    let mut paths: Option<usize> = None;
+    let mut samples: Option<usize> = None;
+    let mut splitters = false;
+    let mut counts_display: Option<CountsDisplay> = None;
+    let mut csv: Option<PathBuf> = None;
+    let mut edge = EdgePolicy::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--exact" => count_mode = Some(CountMode::Exact),
+            "--modulo" => {
+                let value = options.next().context("Missing value for --modulo")?;
+                count_mode =
+                    Some(CountMode::Modulo(value.parse().with_context(|| {
+                        format!("Invalid value for --modulo: '{}'", value)
+                    })?));
+            }
+            "--paths" | "--sample" => {
+                let value = options
+                    .next()
+                    .with_context(|| format!("Missing value for {}", option))?;
+                let value = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for {}: '{}'", option, value))?;
+                if option == "--paths" {
+                    paths = Some(value);
+                } else {
+                    samples = Some(value);
+                }
+            }
+            "--splitters" => splitters = true,
+            "--counts" => {
+                let value = options.next().context("Missing value for --counts")?;
+                counts_display = Some(match value.as_str() {
+                    "fixed" => CountsDisplay::FixedWidth,
+                    "linear" => CountsDisplay::Colour(HeatmapScale::Linear),
+                    "log" => CountsDisplay::Colour(HeatmapScale::Log),
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid value for --counts: '{}'. Expected 'fixed', 'linear' or 'log'",
+                            other
+                        ));
+                    }
+                });
+            }
+            "--csv" => csv = Some(options.next().context("Missing value for --csv")?.into()),
+            "--edge" => {
+                let value = options.next().context("Missing value for --edge")?;
+                edge = match value.as_str() {
+                    "lost" => EdgePolicy::Lost,
+                    "reflected" => EdgePolicy::Reflected,
+                    "error" => EdgePolicy::Error,
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid value for --edge: '{}'. Expected 'lost', 'reflected' or 'error'",
+                            other
+                        ));
+                    }
+                };
+            }
+            _ => {
+                if !image.parse_option(option, &mut options)? {
+                    return Err(anyhow!("Unknown option for day_7: '{}'", option));
+                }
+            }
+        }
+    }
+
+    let puzzle = parse_day!(day_7);
+    let beams = Beams::trace_with_policy(&puzzle, edge)?;
+
+    let solution_only = count_mode.is_none()
+        && paths.is_none()
+        && samples.is_none()
+        && !splitters
+        && counts_display.is_none()
+        && csv.is_none()
+        && !image.is_set();
+    if solution_only {
+        println!(
+            "Solution:\n- Task 1: {}\n- Task 2: {}",
+            beams.splits(),
+            beams.timelines()?
+        );
+    }
+
+    if let Some(mode) = count_mode {
+        let count = beams.timeline_count(mode)?;
+        match mode {
+            CountMode::Exact => println!("Timelines: {}", count),
+            CountMode::Modulo(modulus) => println!("Timelines mod {}: {}", modulus, count),
+        }
+    }
+
+    if let Some(paths) = paths {
+        for timeline in beams.enumerate()?.take(paths) {
+            println!("{}", timeline);
+        }
+    }
+
+    if let Some(samples) = samples {
+        let mut rng = rand::rng();
+        for _ in 0..samples {
+            match beams.sample(&mut rng)? {
+                Some(timeline) => println!("{}", timeline),
+                None => println!("No timeline"),
+            }
+        }
+    }
+
+    if splitters {
+        for stats in beams.splitter_stats()? {
+            println!("{}", stats);
+        }
+    }
+
+    if let Some(display) = counts_display {
+        print!("{}", render_counts(&puzzle, &beams, display));
+    }
+
+    if let Some(path) = csv {
+        std::fs::write(&path, counts_csv(&puzzle, &beams))
+            .with_context(|| format!("Failed to write file {}", path.display()))?;
+        println!("Wrote {}", path.display());
+    }
+
+    if image.is_set() {
+        image.export(&timeline_grid(&puzzle, &beams))?;
+    }
+
+    Ok(())
+}
+
 #[macro_export]
-macro_rules! run_day {
+macro_rules! parse_day {
     ($day:ident) => {{
         let input = match common::read_input(stringify!($day)) {
             Ok(input) => input,
@@ -44,7 +560,7 @@ macro_rules! run_day {
         };
 
         // Consume input
-        let puzzle = match aoc_2025::$day::parse_puzzle(&input) {
+        match aoc_2025::$day::parse_puzzle(&input) {
             Ok(puzzle) => puzzle,
             Err(e) => {
                 return Err(anyhow!(
@@ -53,10 +569,26 @@ macro_rules! run_day {
                     e
                 ));
             }
-        };
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! run_day {
+    ($day:ident) => {{
+        let puzzle = parse_day!($day);
 
         // Consume puzzle and pass ownership to solver
-        let solution = aoc_2025::$day::solve_puzzle(puzzle);
+        let solution = match aoc_2025::$day::solve_puzzle(puzzle).into_solution() {
+            Ok(solution) => solution,
+            Err(e) => {
+                return Err(anyhow!(
+                    "Error solving puzzle for {}: {}",
+                    stringify!($day),
+                    e
+                ));
+            }
+        };
 
         println!(
             "Solution:\n- Task 1: {}\n- Task 2: {}",
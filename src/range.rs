@@ -1,38 +1,167 @@
-use std::{fmt::Display, ops::RangeInclusive};
+use std::{fmt::Debug, fmt::Display, ops::RangeInclusive, str::FromStr};
 
 use anyhow::{Context, Result, anyhow};
 
-/// Inclusive range from start to end
-#[derive(Debug, PartialEq)]
-pub struct Range {
-    start: usize,
-    end: usize,
+/// Integer types usable as range bounds
+pub trait Integer: Copy + Ord + Debug + Display + FromStr {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// `self + 1`, `None` on overflow
+    fn checked_succ(self) -> Option<Self>;
+
+    /// `self - 1`, `None` on overflow
+    fn checked_pred(self) -> Option<Self>;
+
+    /// `to - self`, for `self <= to`
+    fn distance(self, to: Self) -> u128;
 }
 
-impl Range {
-    pub fn range(&self) -> RangeInclusive<usize> {
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn checked_pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn distance(self, to: Self) -> u128 {
+                    // Difference is correct modulo 2^128, and fits in u128 for every type
+                    (to as u128).wrapping_sub(self as u128)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+/// Inclusive range from start to end.
+/// Half-open and unbounded ranges are stored with inclusive bounds (`3..7` is `[3, 6]`, `3..` is `[3, MAX]`).
+/// Empty ranges have `end < start`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Range<T = usize> {
+    start: T,
+    end: T,
+}
+
+impl<T: Integer> Range<T> {
+    pub fn new(start: T, end: T) -> Result<Self> {
+        if end < start {
+            return Err(anyhow!(
+                "Cannot create inclusive range where end < start: [{}, {}]",
+                start,
+                end
+            ));
+        }
+        Ok(Range { start, end })
+    }
+
+    /// Range from start (included) to end (excluded), empty if `start == end`
+    pub fn half_open(start: T, end: T) -> Result<Self> {
+        if end < start {
+            return Err(anyhow!(
+                "Cannot create half-open range where end < start: [{}, {})",
+                start,
+                end
+            ));
+        }
+        Ok(end
+            .checked_pred()
+            .filter(|&end| start <= end)
+            .map(|end| Range { start, end })
+            .unwrap_or_else(Self::empty))
+    }
+
+    /// Range from start to the largest value
+    pub fn at_least(start: T) -> Self {
+        Range { start, end: T::MAX }
+    }
+
+    /// Range from the smallest value to end
+    pub fn at_most(end: T) -> Self {
+        Range { start: T::MIN, end }
+    }
+
+    pub fn full() -> Self {
+        Range {
+            start: T::MIN,
+            end: T::MAX,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Range {
+            start: T::MAX,
+            end: T::MIN,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn range(&self) -> RangeInclusive<T> {
         self.start..=self.end
     }
 
-    /// Returns the number of elements in range
-    pub fn count(&self) -> usize {
-        self.end - self.start + 1
+    /// Returns the number of elements in range (saturates for the full `u128` and `i128` ranges)
+    pub fn count(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.start.distance(self.end).saturating_add(1)
     }
 
+    /// Parses any of the following forms, where `a` and `b` are integers (possibly negative):
+    /// - inclusive: `a-b`, `a..=b`, `[a,b]`
+    /// - half-open: `a..b`, `[a,b)`, `(a,b]`, `(a,b)`
+    /// - unbounded: `a..`, `..b`, `..=b`, `..`
     pub fn parse(str: &str) -> Result<Self> {
-        let x: Vec<&str> = str.split('-').collect();
-        if x.len() != 2 {
+        if str.starts_with('[') || str.starts_with('(') {
+            Self::parse_interval(str)
+        } else if str.contains("..") {
+            Self::parse_rust(str)
+        } else {
+            Self::parse_dash(str)
+        }
+    }
+
+    /// `a-b`
+    fn parse_dash(str: &str) -> Result<Self> {
+        // Separator is the first '-' after a digit, others are signs
+        let separator = str
+            .char_indices()
+            .skip(1)
+            .find(|&(i, c)| c == '-' && str[..i].ends_with(|c: char| c.is_ascii_digit()))
+            .map(|(i, _)| i);
+
+        let Some(separator) = separator else {
             return Err(anyhow!(
                 "Invalid range format, expected x-y, found '{}'",
                 str
             ));
-        }
-        let start: usize = x[0]
-            .parse()
-            .with_context(|| format!("Failed to parse range's start: '{}'", x[0]))?;
-        let end: usize = x[1]
-            .parse()
-            .with_context(|| format!("Failed to parse range's end: '{}'", x[1]))?;
+        };
+
+        let start = parse_bound(&str[..separator], "start")?;
+        let end = parse_bound(&str[separator + 1..], "end")?;
 
         if end < start {
             return Err(anyhow!(
@@ -44,41 +173,341 @@ impl Range {
         Ok(Range { start, end })
     }
 
-    pub fn is_included(&self, n: usize) -> bool {
+    /// `a..b`, `a..=b`, with optional bounds
+    fn parse_rust(str: &str) -> Result<Self> {
+        let (start, end, inclusive) = match str.split_once("..=") {
+            Some((start, end)) => (start, end, true),
+            None => {
+                let (start, end) = str.split_once("..").with_context(|| {
+                    format!("Invalid range format, expected a..b, found '{}'", str)
+                })?;
+                (start, end, false)
+            }
+        };
+
+        let start: T = if start.is_empty() {
+            T::MIN
+        } else {
+            parse_bound(start, "start")?
+        };
+
+        match (end.is_empty(), inclusive) {
+            (true, true) => Err(anyhow!("Inclusive range must have an end, found '{}'", str)),
+            (true, false) => Ok(Self::at_least(start)),
+            (false, true) => Self::new(start, parse_bound(end, "end")?)
+                .with_context(|| format!("Invalid range: '{}'", str)),
+            (false, false) => Self::half_open(start, parse_bound(end, "end")?)
+                .with_context(|| format!("Invalid range: '{}'", str)),
+        }
+    }
+
+    /// `[a,b]`, `[a,b)`, `(a,b]` or `(a,b)`
+    fn parse_interval(str: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid interval format, expected [a,b), found '{}'", str);
+
+        let mut chars = str.chars();
+        let start_included = chars.next().ok_or_else(invalid)? == '[';
+        let end_included = match chars.next_back().ok_or_else(invalid)? {
+            ']' => true,
+            ')' => false,
+            _ => return Err(invalid()),
+        };
+
+        let (start, end) = chars.as_str().split_once(',').ok_or_else(invalid)?;
+        let start: T = parse_bound(start.trim(), "start")?;
+        let end: T = parse_bound(end.trim(), "end")?;
+
+        if end < start {
+            return Err(anyhow!(
+                "Cannot create interval where end < start: '{}'",
+                str
+            ));
+        }
+
+        let start = if start_included {
+            Some(start)
+        } else {
+            start.checked_succ()
+        };
+        let end = if end_included {
+            Some(end)
+        } else {
+            end.checked_pred()
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => Ok(Range { start, end }),
+            _ => Ok(Self::empty()),
+        }
+    }
+
+    pub fn is_included(&self, n: T) -> bool {
         self.start <= n && n <= self.end
     }
 }
 
-impl Display for Range {
+fn parse_bound<T: FromStr>(str: &str, name: &str) -> Result<T> {
+    str.parse()
+        .map_err(|_| anyhow!("Failed to parse range's {}: '{}'", name, str))
+}
+
+impl<T: Integer> Display for Range<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{}, {}]", self.start, self.end)
+        if self.is_empty() {
+            write!(f, "[]")
+        } else {
+            write!(f, "[{}, {}]", self.start, self.end)
+        }
     }
 }
 
-pub fn merge_ranges(mut ranges: Vec<Range>) -> Vec<Range> {
+/// Merges overlapping ranges, and adjacent ranges (3-5 and 6-8) if `merge_adjacent` is set.
+/// Empty ranges are dropped.
+pub fn merge_ranges<T: Integer>(mut ranges: Vec<Range<T>>, merge_adjacent: bool) -> Vec<Range<T>> {
     // Soring is needed to simplify the logic of merging. We know the range we see has a start that is bigger or equal to the last seen.
     // Thanks to that, we only need to compare to the last range, not to all seen ranges. A new range cannot create an overlap between 2 previously seen ranges.
-    ranges.sort_by(|a, b| a.start.cmp(&b.start));
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
 
-    let mut merged_ranges: Vec<Range> = Vec::new();
+    let mut merged_ranges: Vec<Range<T>> = Vec::new();
 
     for range in ranges {
         match merged_ranges.last_mut() {
-            Some(last_range) => {
-                if range.start <= last_range.end {
-                    // Overlap, merge
-                    last_range.end = last_range.end.max(range.end);
-                } else {
-                    merged_ranges.push(range);
-                }
+            Some(last_range)
+                if range.start <= last_range.end
+                    || (merge_adjacent && last_range.end.checked_succ() == Some(range.start)) =>
+            {
+                // Overlap, merge
+                last_range.end = last_range.end.max(range.end);
             }
-            None => merged_ranges.push(range),
+            _ => merged_ranges.push(range),
         }
     }
 
     merged_ranges
 }
 
+/// Set of integers stored as sorted, non-overlapping and non-adjacent ranges
+#[derive(Debug, PartialEq, Clone)]
+pub struct RangeSet<T = usize> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Integer> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Integer> RangeSet<T> {
+    pub fn new() -> Self {
+        RangeSet { ranges: Vec::new() }
+    }
+
+    pub fn from_ranges(ranges: Vec<Range<T>>) -> Self {
+        RangeSet {
+            ranges: merge_ranges(ranges, true),
+        }
+    }
+
+    /// Ranges in increasing order
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Total number of elements in the set (saturates like [`Range::count`])
+    pub fn count(&self) -> u128 {
+        self.ranges
+            .iter()
+            .fold(0, |acc: u128, range| acc.saturating_add(range.count()))
+    }
+
+    /// Binary search, O(log n) in the number of ranges
+    pub fn contains(&self, n: T) -> bool {
+        let index = self.ranges.partition_point(|range| range.end < n);
+        self.ranges.get(index).is_some_and(|range| range.start <= n)
+    }
+
+    /// Number of `sorted_ids` in the set, in a single sweep over ids and ranges: O(n + m).
+    /// Faster than calling `contains` for each id when there are many ids.
+    pub fn count_contained_sorted(&self, sorted_ids: &[T]) -> usize {
+        let mut ranges = self.ranges.iter().peekable();
+        let mut count = 0;
+
+        for &id in sorted_ids {
+            // Skip ranges entirely before id, they are before all next ids as well
+            while ranges.next_if(|range| range.end < id).is_some() {}
+
+            match ranges.peek() {
+                Some(range) if range.start <= id => count += 1,
+                Some(_) => (),
+                None => break,
+            }
+        }
+
+        count
+    }
+
+    /// Range of the set closest to `n` (containing it if any), the lowest one on ties.
+    /// Returns the range and its distance to `n` (0 if `n` is in the range).
+    pub fn nearest(&self, n: T) -> Option<(&Range<T>, u128)> {
+        let index = self.ranges.partition_point(|range| range.end < n);
+
+        // Closest candidates: the first range ending after n, and the one before
+        let after = self.ranges.get(index).map(|range| {
+            (
+                range,
+                if range.start <= n {
+                    0
+                } else {
+                    n.distance(range.start)
+                },
+            )
+        });
+        let before = index
+            .checked_sub(1)
+            .map(|i| (&self.ranges[i], self.ranges[i].end.distance(n)));
+
+        match (before, after) {
+            (Some(before), Some(after)) if after.1 < before.1 => Some(after),
+            (Some(before), _) => Some(before),
+            (None, after) => after,
+        }
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Ranges overlapping or adjacent to `range` are in first..last
+        let first = self
+            .ranges
+            .partition_point(|r| r.end.checked_succ().is_some_and(|end| end < range.start));
+        let last = self
+            .ranges
+            .partition_point(|r| range.end.checked_succ().is_none_or(|end| r.start <= end));
+
+        if first == last {
+            self.ranges.insert(first, range);
+            return;
+        }
+
+        let merged = Range {
+            start: range.start.min(self.ranges[first].start),
+            end: range.end.max(self.ranges[last - 1].end),
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn remove(&mut self, range: &Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Ranges overlapping `range` are in first..last
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+
+        if first == last {
+            return;
+        }
+
+        // Keep what sticks out on each side (bounds can't overflow, they are strictly inside another range)
+        let mut remaining: Vec<Range<T>> = Vec::with_capacity(2);
+        if let Some(end) = range.start.checked_pred()
+            && self.ranges[first].start <= end
+        {
+            remaining.push(Range {
+                start: self.ranges[first].start,
+                end,
+            });
+        }
+        if let Some(start) = range.end.checked_succ()
+            && start <= self.ranges[last - 1].end
+        {
+            remaining.push(Range {
+                start,
+                end: self.ranges[last - 1].end,
+            });
+        }
+
+        self.ranges.splice(first..last, remaining);
+    }
+
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut res = self.clone();
+        for range in &other.ranges {
+            res.insert(range.clone());
+        }
+        res
+    }
+
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+
+        // Both lists are sorted: advance in whichever range ends first
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start <= end {
+                ranges.push(Range { start, end });
+            }
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        RangeSet { ranges }
+    }
+
+    /// Elements of `self` that are not in `other`
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut res = self.clone();
+        for range in &other.ranges {
+            res.remove(range);
+        }
+        res
+    }
+
+    /// Elements of `bounds` that are not in the set
+    pub fn complement(&self, bounds: &Range<T>) -> RangeSet<T> {
+        RangeSet::from_ranges(vec![bounds.clone()]).difference(self)
+    }
+
+    /// Ranges between consecutive ranges of the set
+    pub fn gaps(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        // Ranges are not adjacent, so there is at least one value between them
+        self.ranges.windows(2).filter_map(|pair| {
+            Some(Range {
+                start: pair[0].end.checked_succ()?,
+                end: pair[1].start.checked_pred()?,
+            })
+        })
+    }
+}
+
+impl<T: Integer> Display for RangeSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        write!(f, "}}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,7 +515,7 @@ mod tests {
     #[test]
     fn test_parse_empty_range() {
         let input = "";
-        let range = Range::parse(input);
+        let range: Result<Range> = Range::parse(input);
         assert!(range.is_err());
         assert!(
             range
@@ -99,7 +528,7 @@ mod tests {
     #[test]
     fn test_parse_invalid_range() {
         let input = "2-1";
-        let range = Range::parse(input);
+        let range: Result<Range> = Range::parse(input);
         assert!(range.is_err());
         assert!(
             range
@@ -137,7 +566,178 @@ mod tests {
             new_range(16, 20),
             new_range(12, 18),
         ];
-        let merged_ranged = merge_ranges(ranges);
+        let merged_ranged = merge_ranges(ranges, false);
         assert_eq!(merged_ranged, vec![new_range(3, 5), new_range(10, 20)])
     }
+
+    #[test]
+    fn test_merge_ranges_adjacent() {
+        let ranges = || vec![Range::new(3, 5).unwrap(), Range::new(6, 8).unwrap()];
+        assert_eq!(merge_ranges(ranges(), false), ranges());
+        assert_eq!(
+            merge_ranges(ranges(), true),
+            vec![Range::new(3, 8).unwrap()]
+        );
+
+        let ranges = vec![
+            Range::new(i64::MIN, -1).unwrap(),
+            Range::new(0, i64::MAX).unwrap(),
+        ];
+        assert_eq!(merge_ranges(ranges, true), vec![Range::full()]);
+    }
+
+    #[test]
+    fn test_parse_signed_range() {
+        let range: Range<i64> = Range::parse("-5--3").unwrap();
+        assert_eq!(range, Range { start: -5, end: -3 });
+        let range: Range<i64> = Range::parse("-5-3").unwrap();
+        assert_eq!(range, Range { start: -5, end: 3 });
+        assert_eq!(range.count(), 9);
+        assert!(Range::<usize>::parse("-5-3").is_err());
+    }
+
+    #[test]
+    fn test_parse_rust_syntax() {
+        let parse = |str| Range::<i64>::parse(str).unwrap();
+        assert_eq!(parse("3..=7"), Range { start: 3, end: 7 });
+        assert_eq!(parse("3..7"), Range { start: 3, end: 6 });
+        assert_eq!(parse("-3..0"), Range { start: -3, end: -1 });
+        assert_eq!(parse("3.."), Range::at_least(3));
+        assert_eq!(parse("..3"), Range::at_most(2));
+        assert_eq!(parse("..=3"), Range::at_most(3));
+        assert_eq!(parse(".."), Range::full());
+        assert!(parse("3..3").is_empty());
+        assert!(Range::<usize>::parse("0..0").unwrap().is_empty());
+        assert!(Range::<i64>::parse("7..3").is_err());
+        assert!(Range::<i64>::parse("3..=").is_err());
+    }
+
+    #[test]
+    fn test_parse_interval_syntax() {
+        let parse = |str| Range::<u128>::parse(str).unwrap();
+        assert_eq!(parse("[3,7]"), Range { start: 3, end: 7 });
+        assert_eq!(parse("[3, 7)"), Range { start: 3, end: 6 });
+        assert_eq!(parse("(3,7]"), Range { start: 4, end: 7 });
+        assert_eq!(parse("(3,7)"), Range { start: 4, end: 6 });
+        assert_eq!(parse("[3,3]"), Range { start: 3, end: 3 });
+        assert!(parse("[3,3)").is_empty());
+        assert!(parse("(3,4)").is_empty());
+        assert!(Range::<u128>::parse("[7,3]").is_err());
+        assert!(Range::<u128>::parse("[3;7]").is_err());
+    }
+
+    #[test]
+    fn test_count_wide_ranges() {
+        assert_eq!(Range::<u128>::full().count(), u128::MAX);
+        assert_eq!(Range::<i64>::full().count(), 1 << 64);
+        assert_eq!(Range::<i8>::empty().count(), 0);
+        assert_eq!(Range::<i8>::empty().to_string(), "[]");
+    }
+
+    fn set(ranges: &[(usize, usize)]) -> RangeSet {
+        RangeSet::from_ranges(
+            ranges
+                .iter()
+                .map(|&(start, end)| Range::new(start, end).unwrap())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_range_set_from_ranges_merges_adjacent() {
+        let range_set = set(&[(6, 8), (3, 5), (10, 14), (12, 18)]);
+        assert_eq!(range_set, set(&[(3, 8), (10, 18)]));
+        assert_eq!(range_set.to_string(), "{[3, 8], [10, 18]}");
+        assert_eq!(range_set.count(), 15);
+    }
+
+    #[test]
+    fn test_range_set_contains() {
+        let range_set = set(&[(3, 5), (10, 14), (20, 20)]);
+        let contained: Vec<usize> = (0..25).filter(|&n| range_set.contains(n)).collect();
+        assert_eq!(contained, vec![3, 4, 5, 10, 11, 12, 13, 14, 20]);
+        assert!(!RangeSet::new().contains(0));
+    }
+
+    #[test]
+    fn test_range_set_count_contained_sorted() {
+        let range_set = set(&[(3, 5), (10, 14), (20, 20)]);
+        let ids: Vec<usize> = (0..25).chain([25, 25, 30]).collect();
+        assert_eq!(range_set.count_contained_sorted(&ids), 9);
+        assert_eq!(range_set.count_contained_sorted(&[4, 4, 20, 20]), 4);
+        assert_eq!(RangeSet::new().count_contained_sorted(&[1, 2]), 0);
+    }
+
+    #[test]
+    fn test_range_set_nearest() {
+        let range_set = set(&[(3, 5), (10, 14), (20, 20)]);
+        let nearest = |n| range_set.nearest(n).map(|(r, d)| (r.start, d));
+        assert_eq!(nearest(0), Some((3, 3)));
+        assert_eq!(nearest(4), Some((3, 0)));
+        assert_eq!(nearest(7), Some((3, 2)));
+        // Tie: lowest range
+        assert_eq!(nearest(17), Some((10, 3)));
+        assert_eq!(nearest(18), Some((20, 2)));
+        assert_eq!(nearest(100), Some((20, 80)));
+        assert_eq!(RangeSet::<usize>::new().nearest(1), None);
+    }
+
+    #[test]
+    fn test_range_set_insert() {
+        let mut range_set = set(&[(3, 5), (10, 14), (20, 22)]);
+        range_set.insert(Range::new(0, 1).unwrap());
+        assert_eq!(range_set, set(&[(0, 1), (3, 5), (10, 14), (20, 22)]));
+        range_set.insert(Range::new(6, 19).unwrap());
+        assert_eq!(range_set, set(&[(0, 1), (3, 22)]));
+        range_set.insert(Range::new(2, 2).unwrap());
+        assert_eq!(range_set, set(&[(0, 22)]));
+        range_set.insert(Range::new(30, usize::MAX).unwrap());
+        assert_eq!(range_set, set(&[(0, 22), (30, usize::MAX)]));
+    }
+
+    #[test]
+    fn test_range_set_remove() {
+        let mut range_set = set(&[(3, 5), (10, 14), (20, 22)]);
+        range_set.remove(&Range::new(6, 9).unwrap());
+        assert_eq!(range_set, set(&[(3, 5), (10, 14), (20, 22)]));
+        range_set.remove(&Range::new(12, 12).unwrap());
+        assert_eq!(range_set, set(&[(3, 5), (10, 11), (13, 14), (20, 22)]));
+        range_set.remove(&Range::new(4, 21).unwrap());
+        assert_eq!(range_set, set(&[(3, 3), (22, 22)]));
+        range_set.remove(&Range::new(0, usize::MAX).unwrap());
+        assert!(range_set.is_empty());
+    }
+
+    #[test]
+    fn test_range_set_signed() {
+        let mut range_set: RangeSet<i64> = RangeSet::from_ranges(vec![Range::full()]);
+        range_set.remove(&Range::new(-10, 10).unwrap());
+        assert_eq!(
+            range_set.gaps().collect::<Vec<Range<i64>>>(),
+            vec![Range::new(-10, 10).unwrap()]
+        );
+        assert!(range_set.contains(i64::MIN));
+        assert!(!range_set.contains(0));
+        range_set.insert(Range::new(-10, 10).unwrap());
+        assert_eq!(range_set.ranges(), &[Range::full()]);
+    }
+
+    #[test]
+    fn test_range_set_operations() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25), (40, 50)]);
+
+        assert_eq!(a.union(&b), set(&[(0, 30), (40, 50)]));
+        assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25)]));
+        assert_eq!(a.difference(&b), set(&[(0, 4), (26, 30)]));
+        assert_eq!(b.difference(&a), set(&[(11, 19), (40, 50)]));
+        assert_eq!(
+            a.complement(&Range::new(0, 40).unwrap()),
+            set(&[(11, 19), (31, 40)])
+        );
+        assert_eq!(
+            a.gaps().collect::<Vec<Range>>(),
+            vec![Range::new(11, 19).unwrap()]
+        );
+    }
 }
@@ -0,0 +1,115 @@
+use num_bigint::BigUint;
+
+/// Indices of the lexicographically largest subsequence of `k` elements of `values`.
+/// When equal elements compete, the earliest ones are picked.
+/// Returns `None` if there are fewer than `k` values.
+pub fn max_subsequence<T: Ord>(values: &[T], k: usize) -> Option<Vec<usize>> {
+    subsequence_by(values, k, |value, last| value > last)
+}
+
+/// Indices of the lexicographically smallest subsequence of `k` elements of `values`.
+/// When equal elements compete, the earliest ones are picked.
+/// Returns `None` if there are fewer than `k` values.
+pub fn min_subsequence<T: Ord>(values: &[T], k: usize) -> Option<Vec<usize>> {
+    subsequence_by(values, k, |value, last| value < last)
+}
+
+/// Lexicographically largest subsequence of `k` characters of `str`
+pub fn max_string_subsequence(str: &str, k: usize) -> Option<String> {
+    let chars: Vec<char> = str.chars().collect();
+    max_subsequence(&chars, k).map(|indices| indices.iter().map(|&i| chars[i]).collect())
+}
+
+/// Lexicographically smallest subsequence of `k` characters of `str`
+pub fn min_string_subsequence(str: &str, k: usize) -> Option<String> {
+    let chars: Vec<char> = str.chars().collect();
+    min_subsequence(&chars, k).map(|indices| indices.iter().map(|&i| chars[i]).collect())
+}
+
+/// Number written with `digits` in base 10, most significant digit first.
+/// Does not overflow, whatever the number of digits.
+pub fn digits_to_number(digits: &[usize]) -> BigUint {
+    digits
+        .iter()
+        .fold(BigUint::ZERO, |acc, &digit| acc * 10u32 + digit)
+}
+
+/// Monotonic stack: `replaces(value, last)` tells if `value` should replace `last` in the subsequence
+fn subsequence_by<T>(
+    values: &[T],
+    k: usize,
+    replaces: impl Fn(&T, &T) -> bool,
+) -> Option<Vec<usize>> {
+    let mut to_remove = values.len().checked_sub(k)?;
+
+    let mut res: Vec<usize> = Vec::with_capacity(values.len());
+
+    // Greedily remove all previous values that `value` replaces
+    // Adds between [values.len() - to_remove = k, values.len()] indices
+    for (index, value) in values.iter().enumerate() {
+        // Remove stored values until either
+        // - there is nothing to remove
+        // - the last stored value is not replaced
+        // This makes sense because the first values in `res` have the most importance, if they can be replaced, they must.
+        while to_remove > 0
+            && let Some(&last) = res.last()
+            && replaces(value, &values[last])
+        {
+            // We remove a value here, so increase count
+            res.pop();
+            to_remove -= 1;
+        }
+
+        res.push(index);
+    }
+
+    // Trim what is taken in excess (happens if nothing was removed, for example if values are already ordered)
+    res.truncate(k);
+
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_subsequence() {
+        assert_eq!(
+            max_subsequence(&[2, 4, 3, 5, 2, 3, 4, 2], 4),
+            Some(vec![3, 5, 6, 7])
+        );
+        assert_eq!(max_subsequence(&[9, 9, 8], 2), Some(vec![0, 1]));
+        assert_eq!(max_subsequence(&[1, 2, 3], 3), Some(vec![0, 1, 2]));
+        assert_eq!(max_subsequence(&[1, 2, 3], 0), Some(vec![]));
+        assert_eq!(max_subsequence(&[1, 2, 3], 4), None);
+    }
+
+    #[test]
+    fn test_min_subsequence() {
+        assert_eq!(
+            min_subsequence(&[3, 1, 4, 1, 5, 9, 2, 6], 3),
+            Some(vec![1, 3, 6])
+        );
+        assert_eq!(min_subsequence(&[1, 1, 2], 2), Some(vec![0, 1]));
+        assert_eq!(min_subsequence::<usize>(&[], 1), None);
+    }
+
+    #[test]
+    fn test_string_subsequence() {
+        assert_eq!(max_string_subsequence("advent", 3), Some("vnt".to_string()));
+        assert_eq!(min_string_subsequence("advent", 3), Some("ade".to_string()));
+        assert_eq!(max_string_subsequence("🦀a🎄", 2), Some("🦀🎄".to_string()));
+        assert_eq!(min_string_subsequence("ab", 3), None);
+    }
+
+    #[test]
+    fn test_digits_to_number() {
+        assert_eq!(digits_to_number(&[]), BigUint::ZERO);
+        assert_eq!(digits_to_number(&[0, 4, 2]), BigUint::from(42u32));
+
+        let digits = vec![9; 40];
+        let expected: BigUint = "9".repeat(40).parse().unwrap();
+        assert_eq!(digits_to_number(&digits), expected);
+    }
+}